@@ -286,32 +286,102 @@ impl<U> Sum for Unit<U> {
     }
 }
 
-/// A type for quantities along with their unit
+/// A type for quantities along with their unit, as they can be written in TeX (e.g. `\rule{2pt}{1ex}` or `\mkern3mu`)
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum AnyUnit {
     /// em
     Em(f64),
+    /// ex, the x-height of the font
+    Ex(f64),
+    /// math unit, 1/18 em (in the current style)
+    Mu(f64),
     /// pixels
-    Px(f64)
+    Px(f64),
+    /// TeX point, 1/72.27 in
+    Pt(f64),
+    /// big point (aka desktop publishing point), 1/72 in
+    Bp(f64),
+    /// pica, 12 pt
+    Pc(f64),
+    /// didot point, 1238/1157 pt
+    Dd(f64),
+    /// scaled point, 1/65536 pt
+    Sp(f64),
+    /// centimeter
+    Cm(f64),
+    /// millimeter
+    Mm(f64),
+    /// inch
+    In(f64),
+}
+
+/// The units of [`AnyUnit`] which depend on the font or on the rendering (cf [`AnyUnit::split_physical`])
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(crate) enum RelativeUnit {
+    Em(f64),
+    Ex(f64),
+    Mu(f64),
+    Px(f64),
 }
 
 impl AnyUnit {
     /// Check if the length represented is negative. 
     /// (We implicitly assume that all units represented within this type are *positive* multiples of each other)
     pub fn is_negative(&self) -> bool {
-        match self {
-            AnyUnit::Em(val) => val.is_sign_negative(),
-            AnyUnit::Px(val) => val.is_sign_negative(),
+        self.value().is_sign_negative()
+    }
+
+    /// For physical units (pt, cm, in, etc.), the length in pixels at the standard PPI (cf [`Inch`]).  
+    /// Returns `None` for units which depend on the font or on the rendering (em, ex, mu and px).
+    pub fn to_standard_px(self) -> Option<Unit<Px>> {
+        self.split_physical().ok()
+    }
+
+    /// Like [`AnyUnit::to_standard_px`], but gives back the length in a unit which depends on the font or on the rendering instead of `None`.
+    pub(crate) fn split_physical(self) -> Result<Unit<Px>, RelativeUnit> {
+        let inches = match self {
+            AnyUnit::Pt(pt)   => pt / 72.27,
+            AnyUnit::Bp(bp)   => bp / 72.,
+            AnyUnit::Pc(pc)   => 12. * pc / 72.27,
+            AnyUnit::Dd(dd)   => 1238. / 1157. * dd / 72.27,
+            AnyUnit::Sp(sp)   => sp / 65536. / 72.27,
+            AnyUnit::Cm(cm)   => cm / 2.54,
+            AnyUnit::Mm(mm)   => mm / 25.4,
+            AnyUnit::In(inch) => inch,
+            AnyUnit::Em(em)   => return Err(RelativeUnit::Em(em)),
+            AnyUnit::Ex(ex)   => return Err(RelativeUnit::Ex(ex)),
+            AnyUnit::Mu(mu)   => return Err(RelativeUnit::Mu(mu)),
+            AnyUnit::Px(px)   => return Err(RelativeUnit::Px(px)),
+        };
+        Ok(Unit::<Inch>::new(inches) * Unit::STANDARD_PPI)
+    }
+
+    fn value(&self) -> f64 {
+        match *self {
+            AnyUnit::Em(val) | AnyUnit::Ex(val) | AnyUnit::Mu(val) | AnyUnit::Px(val)
+            | AnyUnit::Pt(val) | AnyUnit::Bp(val) | AnyUnit::Pc(val) | AnyUnit::Dd(val) 
+            | AnyUnit::Sp(val) | AnyUnit::Cm(val) | AnyUnit::Mm(val) | AnyUnit::In(val) => val,
         }
     }
 }
 
 impl Display for AnyUnit {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            AnyUnit::Em(value) => write!(f, "{}em", value),
-            AnyUnit::Px(value) => write!(f, "{}px", value),
-        }
+        let unit = match self {
+            AnyUnit::Em(_) => "em",
+            AnyUnit::Ex(_) => "ex",
+            AnyUnit::Mu(_) => "mu",
+            AnyUnit::Px(_) => "px",
+            AnyUnit::Pt(_) => "pt",
+            AnyUnit::Bp(_) => "bp",
+            AnyUnit::Pc(_) => "pc",
+            AnyUnit::Dd(_) => "dd",
+            AnyUnit::Sp(_) => "sp",
+            AnyUnit::Cm(_) => "cm",
+            AnyUnit::Mm(_) => "mm",
+            AnyUnit::In(_) => "in",
+        };
+        write!(f, "{}{}", self.value(), unit)
     }
 }
 
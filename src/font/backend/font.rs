@@ -117,6 +117,11 @@ impl MathFont for OpenTypeFont {
             lower_limit_gap_min: em(math_constants.lower_limit_gap_min.value.into()),
             lower_limit_baseline_drop_min: em(math_constants.lower_limit_baseline_drop_min.value.into()),
 
+            stretch_stack_top_shift_up: em(math_constants.stretch_stack_top_shift_up.value.into()),
+            stretch_stack_bottom_shift_down: em(math_constants.stretch_stack_bottom_shift_down.value.into()),
+            stretch_stack_gap_above_min: em(math_constants.stretch_stack_gap_above_min.value.into()),
+            stretch_stack_gap_below_min: em(math_constants.stretch_stack_gap_below_min.value.into()),
+
             fraction_rule_thickness: em(math_constants.fraction_rule_thickness.value.into()),
             fraction_numerator_display_style_shift_up: em(math_constants.fraction_numerator_display_style_shift_up.value.into()),
             fraction_denominator_display_style_shift_down: em(math_constants.fraction_denominator_display_style_shift_down.value.into()),
@@ -129,6 +134,8 @@ impl MathFont for OpenTypeFont {
 
             axis_height: em(math_constants.axis_height.value.into()),
             accent_base_height: em(math_constants.accent_base_height.value.into()),
+            // the OS/2 table is not exposed by this crate; AccentBaseHeight is the MATH table's stand-in for the x-height
+            x_height: em(math_constants.accent_base_height.value.into()),
 
             delimited_sub_formula_min_height: em(math_constants.delimited_sub_formula_min_height.into()),
 
@@ -138,6 +145,9 @@ impl MathFont for OpenTypeFont {
             radical_vertical_gap: em(math_constants.radical_vertical_gap.value.into()),
             radical_rule_thickness: em(math_constants.radical_rule_thickness.value.into()),
             radical_extra_ascender: em(math_constants.radical_extra_ascender.value.into()),
+            radical_kern_before_degree: em(math_constants.radical_kern_before_degree.value.into()),
+            radical_kern_after_degree: em(math_constants.radical_kern_after_degree.value.into()),
+            radical_degree_bottom_raise_percent: 0.01 * f64::from(math_constants.radical_degree_bottom_raise_percent),
 
             stack_display_style_gap_min: em(math_constants.stack_display_style_gap_min.value.into()),
             stack_top_display_style_shift_up: em(math_constants.stack_top_display_style_shift_up.value.into()),
@@ -1,9 +1,12 @@
 
 use std::convert::TryInto;
 
-use ttf_parser::{math::GlyphPart, LazyArray16};
+use ttf_parser::{math::GlyphPart, LazyArray16, Tag};
+use ttf_parser::gpos::{PairAdjustment, PositioningSubtable};
+use ttf_parser::gsub::SubstitutionSubtable;
+use ttf_parser::opentype_layout::{LayoutTable, Lookup};
 
-use crate::{font::{Constants, VariantGlyph, common::{GlyphInstruction, GlyphId}, Direction, Glyph}, error::FontError, dimensions::units::Ratio};
+use crate::{font::{Constants, TextFont, VariantGlyph, common::{GlyphInstruction, GlyphId}, Direction, Glyph}, error::FontError, dimensions::units::Ratio};
 use crate::dimensions::Unit;
 use crate::dimensions::units::{Em, FUnit};
 
@@ -11,7 +14,8 @@ use crate::dimensions::units::{Em, FUnit};
 /// A wrapper around 'ttf_parser::Face' which caches some of the needed values.
 /// This wrapper implements the 'MathFont' trait needed to do the layout and rendering o
 pub struct TtfMathFont<'a> {
-    math: ttf_parser::math::Table<'a>,
+    /// Absent for fonts only used for text (cf 'TtfMathFont::new_text_font')
+    math: Option<ttf_parser::math::Table<'a>>,
     font: ttf_parser::Face<'a>,
     font_matrix: ttf_parser::cff::Matrix,
 }
@@ -21,6 +25,18 @@ impl<'a> TtfMathFont<'a> {
     /// Fails if font has no MATH table.
     pub fn new(font: ttf_parser::Face<'a>) -> Result<Self, FontError> { 
         let math = font.tables().math.ok_or(FontError::NoMATHTable)?;
+        let mut math_font = Self::without_math_table(font);
+        math_font.math = Some(math);
+        Ok(math_font)
+    }
+
+    /// Creates a text font from any 'ttf_parser::Face', which needs no MATH table.
+    /// Such a font can only be used as the text font of a 'FontContext' (cf 'FontContext::with_text_font').
+    pub fn new_text_font(font: ttf_parser::Face<'a>) -> TextFont<Self> { 
+        TextFont::new(Self::without_math_table(font))
+    }
+
+    fn without_math_table(font: ttf_parser::Face<'a>) -> Self { 
         let font_matrix; 
         if let Some(cff) = font.tables().cff {
             font_matrix = cff.matrix();
@@ -36,11 +52,11 @@ impl<'a> TtfMathFont<'a> {
                 ty: 0.,
             };
         };
-        Ok(Self { 
-            math, 
+        Self { 
+            math: None, 
             font,
             font_matrix,
-        }) 
+        }
     }
     
     /// Returns a reference to the wrapped 'ttf_parser::Face'
@@ -57,7 +73,7 @@ impl<'a> TtfMathFont<'a> {
 
 impl<'a> TtfMathFont<'a> {
     fn safe_italics(&self, glyph_id : GlyphId) -> Option<i16> {
-        let value = self.math.glyph_info?
+        let value = self.math?.glyph_info?
             .italic_corrections?
             .get(glyph_id.into())?
             .value;
@@ -66,7 +82,7 @@ impl<'a> TtfMathFont<'a> {
 
     fn safe_attachment(&self, glyph_id : GlyphId) -> Option<i16> {
         // TODO : cache GlyphInfo table & constants
-        let value = self.math.glyph_info?
+        let value = self.math?.glyph_info?
             .top_accent_attachments?
             .get(glyph_id.into())?
             .value;
@@ -75,7 +91,7 @@ impl<'a> TtfMathFont<'a> {
 
     fn safe_constants(&self, font_units_to_em : Unit<Ratio<Em, FUnit>>) -> Option<Constants> {
         // perhaps cache : GlyphInfo table
-        let math_constants = self.math.constants?;
+        let math_constants = self.math?.constants?;
         let em = |v: f64| -> Unit<Em> { Unit::<FUnit>::new(v) * font_units_to_em };
 
 
@@ -95,6 +111,11 @@ impl<'a> TtfMathFont<'a> {
             lower_limit_gap_min:           em(math_constants.lower_limit_gap_min().value.into()),
             lower_limit_baseline_drop_min: em(math_constants.lower_limit_baseline_drop_min().value.into()),
 
+            stretch_stack_top_shift_up:      em(math_constants.stretch_stack_top_shift_up().value.into()),
+            stretch_stack_bottom_shift_down: em(math_constants.stretch_stack_bottom_shift_down().value.into()),
+            stretch_stack_gap_above_min:     em(math_constants.stretch_stack_gap_above_min().value.into()),
+            stretch_stack_gap_below_min:     em(math_constants.stretch_stack_gap_below_min().value.into()),
+
             fraction_rule_thickness:                       em(math_constants.fraction_rule_thickness().value.into()),
             fraction_numerator_display_style_shift_up:     em(math_constants.fraction_numerator_display_style_shift_up().value.into()),
             fraction_denominator_display_style_shift_down: em(math_constants.fraction_denominator_display_style_shift_down().value.into()),
@@ -107,6 +128,7 @@ impl<'a> TtfMathFont<'a> {
 
             axis_height:        em(math_constants.axis_height().value.into()),
             accent_base_height: em(math_constants.accent_base_height().value.into()),
+            x_height:           em(self.font.x_height().unwrap_or(math_constants.accent_base_height().value).into()),
 
             delimited_sub_formula_min_height: em(math_constants.delimited_sub_formula_min_height().into()),
 
@@ -116,6 +138,9 @@ impl<'a> TtfMathFont<'a> {
             radical_vertical_gap:               em(math_constants.radical_vertical_gap().value.into()),
             radical_rule_thickness:             em(math_constants.radical_rule_thickness().value.into()),
             radical_extra_ascender:             em(math_constants.radical_extra_ascender().value.into()),
+            radical_kern_before_degree:         em(math_constants.radical_kern_before_degree().value.into()),
+            radical_kern_after_degree:          em(math_constants.radical_kern_after_degree().value.into()),
+            radical_degree_bottom_raise_percent: 0.01 * f64::from(math_constants.radical_degree_bottom_raise_percent()),
 
             stack_display_style_gap_min:      em(math_constants.stack_display_style_gap_min().value.into()),
             stack_top_display_style_shift_up: em(math_constants.stack_top_display_style_shift_up().value.into()),
@@ -158,7 +183,7 @@ impl<'a> crate::font::MathFont for TtfMathFont<'a> {
         // NOTE: The following is an adaptation of the corresponding code in the crate "font"
         // NOTE: bizarrely, the code for horizontal variant is not isomorphic to the code for vertical variant ; here, I've simply adapted the vertical variant code
         // TODO: figure out why horiz_variant uses 'greatest_lower_bound' and vert_variant uses 'smallest_lowerè_bound'
-        let variants = match self.math.variants {
+        let variants = match self.math.and_then(|math| math.variants) {
             Some(variants) => variants,
             None => return VariantGlyph::Replacement(gid),
         };
@@ -199,7 +224,7 @@ impl<'a> crate::font::MathFont for TtfMathFont<'a> {
     fn vert_variant(&self, gid: GlyphId, height: crate::dimensions::Unit<FUnit>) -> crate::font::common::VariantGlyph {
         // NOTE: The following is an adaptation of the corresponding code in the crate "font"
 
-        let variants = match self.math.variants {
+        let variants = match self.math.and_then(|math| math.variants) {
             Some(variants) => variants,
             None => return VariantGlyph::Replacement(gid),
         };
@@ -246,8 +271,9 @@ impl<'a> crate::font::MathFont for TtfMathFont<'a> {
 
     fn glyph_from_gid<'f>(&'f self, gid : GlyphId) -> Result<crate::font::Glyph<'f, Self>, FontError> {
         let glyph_id : ttf_parser::GlyphId = gid.into();
-        let bbox     = self.font.glyph_bounding_box(glyph_id).ok_or(FontError::MissingGlyphGID(gid))?;
         let advance  = self.font.glyph_hor_advance(glyph_id).ok_or(FontError::MissingGlyphGID(gid))?;
+        // Glyphs without outline, like the space, have an empty bounding box
+        let bbox     = self.font.glyph_bounding_box(glyph_id).unwrap_or(ttf_parser::Rect { x_min: 0, y_min: 0, x_max: 0, y_max: 0 });
         let lsb  = self.font.glyph_hor_side_bearing(glyph_id).ok_or(FontError::MissingGlyphGID(gid))?;
         let italics = self.italics(gid);
         let attachment = self.attachment(gid);
@@ -269,7 +295,7 @@ impl<'a> crate::font::MathFont for TtfMathFont<'a> {
     }
 
     fn kern_for(&self, glyph_id : GlyphId, height : Unit<FUnit>, side : crate::font::kerning::Corner) -> Option<Unit<FUnit>> {
-        let record = self.math.glyph_info?.kern_infos?.get(glyph_id.into())?;
+        let record = self.math?.glyph_info?.kern_infos?.get(glyph_id.into())?;
 
         let table = match side {
             crate::font::kerning::Corner::TopRight    => record.top_right.as_ref(),
@@ -307,11 +333,92 @@ impl<'a> crate::font::MathFont for TtfMathFont<'a> {
         Unit::<Ratio<Em, FUnit>>::new(self.font_matrix.sx as f64)
     }
 
+    fn ligature(&self, glyphs: &[GlyphId]) -> Option<(GlyphId, usize)> {
+        let (first, rest) = glyphs.split_first()?;
+        for lookup in feature_lookups(self.font.tables().gsub?, b"liga") {
+            for subtable in lookup.subtables.into_iter::<SubstitutionSubtable>() {
+                let SubstitutionSubtable::Ligature(substitution) = subtable else { continue };
+                let Some(ligatures) = substitution.coverage.get((*first).into()).and_then(|index| substitution.ligature_sets.get(index)) else { continue };
+                // Ligatures are ordered by preference, so the first one matching is used
+                for ligature in ligatures {
+                    let components = ligature.components;
+                    if components.len() as usize <= rest.len() && components.into_iter().zip(rest).all(|(component, glyph)| GlyphId::from(component) == *glyph) {
+                        return Some((ligature.glyph.into(), components.len() as usize + 1));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn pair_kerning(&self, left: GlyphId, right: GlyphId) -> Option<Unit<FUnit>> {
+        let (left, right) : (ttf_parser::GlyphId, ttf_parser::GlyphId) = (left.into(), right.into());
+        let gpos_kerning = self.font.tables().gpos.and_then(|gpos| {
+            feature_lookups(gpos, b"kern")
+                .flat_map(|lookup| lookup.subtables.into_iter::<PositioningSubtable>())
+                .find_map(|subtable| match subtable {
+                    PositioningSubtable::Pair(PairAdjustment::Format1 { coverage, sets }) => {
+                        let (record, _) = sets.get(coverage.get(left)?)?.get(right)?;
+                        Some(record.x_advance)
+                    },
+                    PositioningSubtable::Pair(PairAdjustment::Format2 { coverage, classes, matrix }) => {
+                        coverage.get(left)?;
+                        let (record, _) = matrix.get((classes.0.get(left), classes.1.get(right)))?;
+                        Some(record.x_advance)
+                    },
+                    _ => None,
+                })
+        });
+
+        // Older fonts only have a 'kern' table
+        let kerning = gpos_kerning.or_else(|| {
+            self.font.tables().kern?.subtables.into_iter()
+                .filter(|subtable| subtable.horizontal && !subtable.variable)
+                .find_map(|subtable| subtable.glyphs_kerning(left, right))
+        })?;
+        Some(Unit::<FUnit>::new(kerning.into()))
+    }
+
+    fn mark_anchor(&self, base: GlyphId, mark: GlyphId) -> Option<(Unit<FUnit>, Unit<FUnit>)> {
+        let (base, mark) : (ttf_parser::GlyphId, ttf_parser::GlyphId) = (base.into(), mark.into());
+        feature_lookups(self.font.tables().gpos?, b"mark")
+            .flat_map(|lookup| lookup.subtables.into_iter::<PositioningSubtable>())
+            .find_map(|subtable| {
+                let PositioningSubtable::MarkToBase(adjustment) = subtable else { return None };
+                let (class, mark_anchor) = adjustment.marks.get(adjustment.mark_coverage.get(mark)?)?;
+                let base_anchor = adjustment.anchors.get(adjustment.base_coverage.get(base)?, class)?;
+                Some((
+                    Unit::<FUnit>::new((base_anchor.x - mark_anchor.x).into()),
+                    Unit::<FUnit>::new((base_anchor.y - mark_anchor.y).into()),
+                ))
+            })
+    }
+
+    fn is_mark(&self, glyph_id: GlyphId) -> bool {
+        self.font.tables().gdef
+            .and_then(|gdef| gdef.glyph_class(glyph_id.into()))
+            == Some(ttf_parser::gdef::GlyphClass::Mark)
+    }
 
 }
 
 
 
+/// The lookups of a feature (e.g. 'kern') of a GSUB or GPOS table, for the latin script or else the default one
+fn feature_lookups<'a>(table: LayoutTable<'a>, feature: &[u8; 4]) -> impl Iterator<Item = Lookup<'a>> {
+    let tag = Tag::from_bytes(feature);
+    let script = table.scripts.find(Tag::from_bytes(b"latn"))
+        .or_else(|| table.scripts.find(Tag::from_bytes(b"DFLT")));
+    script
+        .and_then(|script| script.default_language)
+        .into_iter()
+        .flat_map(|language| language.feature_indices)
+        .filter_map(move |index| table.features.get(index))
+        .filter(move |feature| feature.tag == tag)
+        .flat_map(|feature| feature.lookup_indices)
+        .filter_map(move |index| table.lookups.get(index))
+}
+
 fn max_overlap(min_connector_overlap : u32, left: &GlyphPart, right: &GlyphPart) -> u32 {
     // NOTE: The following is an adaptation of the corresponding code in the crate "font"
     let overlap = std::cmp::min(left.end_connector_length, right.start_connector_length);
@@ -4,7 +4,7 @@ use super::{Glyph, MathFont};
 use crate::dimensions::{units::{FUnit}, Unit};
 
 /// Corners of a glyph's bounding box
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum Corner {
     /// North-East corner
     TopRight,
@@ -44,14 +44,33 @@ pub enum Corner {
 
 /// Computes the amount of kerning between a base glyph and its superscript
 pub fn superscript_kern<F : MathFont>(base: &Glyph<F>, script: &Glyph<F>, shift: Unit<FUnit>) -> Unit<FUnit> {
+    upper_script_kern(base, script, shift, Corner::TopRight, Corner::BottomLeft)
+}
+
+/// Computes the amount of kerning between a base glyph and its pre-superscript (placed on its left, e.g. with `\sideset`)
+pub fn presuperscript_kern<F : MathFont>(base: &Glyph<F>, script: &Glyph<F>, shift: Unit<FUnit>) -> Unit<FUnit> {
+    upper_script_kern(base, script, shift, Corner::TopLeft, Corner::BottomRight)
+}
+
+/// Computes the amount of kerning between a base glyph and its subscript
+pub fn subscript_kern<F : MathFont>(base: &Glyph<F>, script: &Glyph<F>, shift: Unit<FUnit>) -> Unit<FUnit> {
+    lower_script_kern(base, script, shift, Corner::BottomRight, Corner::TopLeft)
+}
+
+/// Computes the amount of kerning between a base glyph and its pre-subscript (placed on its left, e.g. with `\sideset`)
+pub fn presubscript_kern<F : MathFont>(base: &Glyph<F>, script: &Glyph<F>, shift: Unit<FUnit>) -> Unit<FUnit> {
+    lower_script_kern(base, script, shift, Corner::BottomLeft, Corner::TopRight)
+}
+
+fn upper_script_kern<F : MathFont>(base: &Glyph<F>, script: &Glyph<F>, shift: Unit<FUnit>, base_corner: Corner, script_corner: Corner) -> Unit<FUnit> {
     let base_height = base.bbox.3;
     let script_depth = script.bbox.1 + shift;
 
-    let value1 = kern_from(base, base_height, Corner::TopRight) +
-    kern_from(script, base_height, Corner::BottomLeft);
+    let value1 = kern_from(base, base_height, base_corner) +
+    kern_from(script, base_height, script_corner);
 
-    let value2 = kern_from(base, script_depth, Corner::TopRight) +
-    kern_from(script, script_depth, Corner::BottomLeft);
+    let value2 = kern_from(base, script_depth, base_corner) +
+    kern_from(script, script_depth, script_corner);
 
     if value1 > value2 
     { value1 }
@@ -59,16 +78,15 @@ pub fn superscript_kern<F : MathFont>(base: &Glyph<F>, script: &Glyph<F>, shift:
     { value2 }
 }
 
-/// Computes the amount of kerning between a base glyph and its subscript
-pub fn subscript_kern<F : MathFont>(base: &Glyph<F>, script: &Glyph<F>, shift: Unit<FUnit>) -> Unit<FUnit> {
+fn lower_script_kern<F : MathFont>(base: &Glyph<F>, script: &Glyph<F>, shift: Unit<FUnit>, base_corner: Corner, script_corner: Corner) -> Unit<FUnit> {
     let base_depth = base.bbox.1;
     let script_height = script.bbox.3 - shift;
 
-    let value1 = kern_from(base, base_depth, Corner::BottomRight) +
-    kern_from(script, base_depth, Corner::TopLeft);
+    let value1 = kern_from(base, base_depth, base_corner) +
+    kern_from(script, base_depth, script_corner);
 
-    let value2 = kern_from(base, script_height, Corner::BottomRight) +
-    kern_from(script, script_height, Corner::TopLeft);
+    let value2 = kern_from(base, script_height, base_corner) +
+    kern_from(script, script_height, script_corner);
 
     if value1 < value2 
     { value1 }
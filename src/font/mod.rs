@@ -11,10 +11,12 @@ pub mod backend;
 #[deny(missing_docs)]
 pub mod common;
 mod style;
+mod shaping;
 //mod unit;
 
 pub use unicode_math::TexSymbolType;
-pub use style::style_symbol;
+pub use style::{has_bold_variant, style_symbol};
+pub use shaping::ShapedGlyph;
 
 
 pub use crate::font::common::{Direction, VariantGlyph};
@@ -43,10 +45,39 @@ pub trait MathFont : Sized {
     // the } before "wat?" is too short for the last 2 fonts but not the first
     // maybe this is a problem, maybe this is meant to be
     fn vert_variant(&self, gid: GlyphId, height: Unit<FUnit>) -> VariantGlyph;
+
+    // The following are used to shape text (cf `FontContext::shape_text`) ; by default, a font has no ligatures, kerning or mark anchors.
+
+    /// The ligature replacing the glyphs at the start of `glyphs`, along with the number of glyphs it replaces
+    fn ligature(&self, _glyphs: &[GlyphId]) -> Option<(GlyphId, usize)> { None }
+    /// The kerning to add to the advance of `left` when it is followed by `right`
+    fn pair_kerning(&self, _left: GlyphId, _right: GlyphId) -> Option<Unit<FUnit>> { None }
+    /// The position of the mark `mark` relative to the origin of the base glyph `base` it is attached to
+    fn mark_anchor(&self, _base: GlyphId, _mark: GlyphId) -> Option<(Unit<FUnit>, Unit<FUnit>)> { None }
+    /// Whether the glyph is a mark, i.e. a diacritic placed on the preceding glyph
+    fn is_mark(&self, _glyph_id: GlyphId) -> bool { false }
+}
+
+/// A font used only for text (cf [`FontContext::with_text_font`]), which need not have a MATH table.  
+/// Being a distinct type, it cannot be passed by mistake as the math font of a [`FontContext`].
+pub struct TextFont<F>(F);
+
+impl<F> TextFont<F> {
+    /// Wraps a font to be used for text
+    pub fn new(font: F) -> Self {
+        Self(font)
+    }
+
+    /// The wrapped font ; it is not public so that a font without a MATH table cannot be used as the math font
+    pub(crate) fn font(&self) -> &F {
+        &self.0
+    }
 }
 
 pub struct FontContext<'f, F> {
     pub font: &'f F,
+    /// Font used for text (e.g. `\text{..}` or `\operatorname{..}`), cf [`FontContext::with_text_font`]
+    pub text_font: Option<&'f F>,
     pub constants: Constants,
     pub units_per_em: Unit<Ratio<FUnit, Em>>,
 }
@@ -55,6 +86,7 @@ impl<'f, F> Clone for FontContext<'f, F> {
     fn clone(&self) -> Self {
         Self {
             font:         self.font,
+            text_font:    self.text_font,
             constants:    self.constants.clone(),
             units_per_em: self.units_per_em,
         }
@@ -69,16 +101,70 @@ impl<'f, F : MathFont> FontContext<'f, F> {
 
         FontContext {
             font,
+            text_font: None,
             units_per_em,
             constants
         }
     }
 
+    /// Uses `text_font` for text, e.g. for characters (such as CJK characters) which the math font lacks.
+    /// The text font need not have a MATH table ; characters it lacks are taken from the math font.
+    pub fn with_text_font(self, text_font: &'f TextFont<F>) -> Self {
+        FontContext {
+            text_font: Some(text_font.font()),
+            ..self
+        }
+    }
+
     pub fn glyph(&self, codepoint: char) -> Result<Glyph<'f, F>, FontError> {
         let gid = self.font.glyph_index(codepoint).ok_or(FontError::MissingGlyphCodepoint(codepoint))?;
         self.glyph_from_gid(gid)
     }
 
+    /// The glyph of a character in text, taken from the text font if it has one (cf [`FontContext::with_text_font`]) and from the math font otherwise.  
+    /// The metrics of a glyph from the text font are converted to the font units of the math font, so that it can be laid out like any other glyph.
+    pub fn text_glyph(&self, codepoint: char) -> Result<Glyph<'f, F>, FontError> {
+        let text_font = match self.text_font {
+            Some(text_font) => text_font,
+            None => return self.glyph(codepoint),
+        };
+        let gid = match text_font.glyph_index(codepoint) {
+            Some(gid) => gid,
+            None => return self.glyph(codepoint),
+        };
+        Ok(self.to_math_units(text_font.glyph_from_gid(gid)?))
+    }
+
+    /// The font in which a character of text is set: the text font if it has the character, the math font otherwise
+    fn text_font_for(&self, codepoint: char) -> &'f F {
+        match self.text_font {
+            Some(text_font) if text_font.glyph_index(codepoint).is_some() => text_font,
+            _ => self.font,
+        }
+    }
+
+    /// Converts a length in the font units of `font` to the font units of the math font
+    fn length_to_math_units(&self, font: &F, length: Unit<FUnit>) -> Unit<FUnit> {
+        if std::ptr::eq(font, self.font) {
+            return length;
+        }
+        length * font.font_units_to_em() * self.units_per_em
+    }
+
+    /// Converts the metrics of a glyph (e.g. from the text font) to the font units of the math font
+    fn to_math_units(&self, glyph: Glyph<'f, F>) -> Glyph<'f, F> {
+        let font = glyph.font;
+        let to_math_units = |length: Unit<FUnit>| self.length_to_math_units(font, length);
+        Glyph {
+            bbox: (to_math_units(glyph.bbox.0), to_math_units(glyph.bbox.1), to_math_units(glyph.bbox.2), to_math_units(glyph.bbox.3)),
+            advance:    to_math_units(glyph.advance),
+            lsb:        to_math_units(glyph.lsb),
+            italics:    to_math_units(glyph.italics),
+            attachment: to_math_units(glyph.attachment),
+            ..glyph
+        }
+    }
+
 
 
     pub fn vert_variant(&self, codepoint: char, height: Unit<FUnit>) -> Result<VariantGlyph, FontError> {
@@ -113,6 +199,11 @@ pub struct Constants {
     pub lower_limit_gap_min: Unit<Em>,
     pub lower_limit_baseline_drop_min: Unit<Em>,
 
+    pub stretch_stack_top_shift_up: Unit<Em>,
+    pub stretch_stack_bottom_shift_down: Unit<Em>,
+    pub stretch_stack_gap_above_min: Unit<Em>,
+    pub stretch_stack_gap_below_min: Unit<Em>,
+
     pub fraction_rule_thickness: Unit<Em>,
     pub fraction_numerator_display_style_shift_up: Unit<Em>,
     pub fraction_denominator_display_style_shift_down: Unit<Em>,
@@ -125,6 +216,7 @@ pub struct Constants {
 
     pub axis_height: Unit<Em>,
     pub accent_base_height: Unit<Em>,
+    pub x_height: Unit<Em>,
 
     pub delimited_sub_formula_min_height: Unit<Em>,
     pub display_operator_min_height: Unit<Em>,
@@ -133,6 +225,9 @@ pub struct Constants {
     pub radical_vertical_gap: Unit<Em>,
     pub radical_rule_thickness: Unit<Em>,
     pub radical_extra_ascender: Unit<Em>,
+    pub radical_kern_before_degree: Unit<Em>,
+    pub radical_kern_after_degree: Unit<Em>,
+    pub radical_degree_bottom_raise_percent: f64,
 
     pub stack_display_style_gap_min: Unit<Em>,
     pub stack_top_display_style_shift_up: Unit<Em>,
@@ -205,6 +300,14 @@ impl Style {
         }
     }
 
+    /// The style of `\boldsymbol{..}`: like [`Style::with_bold`], except that symbols which would be italic become bold italic
+    pub fn with_bold_symbol(self) -> Style {
+        match (self.family, self.weight) {
+            (Family::Normal, Weight::None) => self.with_weight(Weight::BoldItalic),
+            _ => self.with_bold(),
+        }
+    }
+
     pub fn with_italics(self) -> Style {
         Style {
             weight: self.weight.with_italics(),
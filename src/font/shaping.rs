@@ -0,0 +1,186 @@
+//! Shapes runs of text, i.e. turns a string into positioned glyphs:
+//!   - characters are mapped to glyphs of the text font, or of the math font when the text font lacks them ;
+//!     in the math font, letters and digits are styled (e.g. bold) with the Mathematical Alphanumeric Symbols
+//!   - ligatures of the font replace sequences of glyphs (e.g. "fi")
+//!   - pairs of glyphs are kerned (e.g. "Ta")
+//!   - combining marks are placed on the glyph preceding them, using the anchors of the font, or centered over it when the font has none
+//!   - spaces get the advance of the font's space glyph
+
+use crate::dimensions::Unit;
+use crate::dimensions::units::{Em, FUnit};
+use crate::error::FontError;
+
+use super::common::GlyphId;
+use super::{style_symbol, FontContext, Glyph, MathFont, Style};
+
+/// Width of a space, for fonts without a space glyph
+const FALLBACK_SPACE_WIDTH : Unit<Em> = Unit::<Em>::new(1. / 3.);
+
+/// A glyph of a shaped run of text (cf [`FontContext::shape_text`]), whose metrics are in font units of the math font
+pub struct ShapedGlyph<'f, F> {
+    /// The glyph to draw, absent for a space
+    pub glyph: Option<Glyph<'f, F>>,
+    /// Horizontal offset of the glyph from the current position
+    pub x_offset: Unit<FUnit>,
+    /// Vertical offset of the glyph from the baseline, upwards
+    pub y_offset: Unit<FUnit>,
+    /// How much the current position moves after the glyph, kerning included ; marks placed on a glyph do not move it
+    pub advance: Unit<FUnit>,
+}
+
+/// The last glyph of a run on which marks are placed, and which is kerned with the next glyph
+struct Base {
+    gid: GlyphId,
+    index: usize,
+    position: Unit<FUnit>,
+    advance: Unit<FUnit>,
+    height: Unit<FUnit>,
+}
+
+impl<'f, F : MathFont> FontContext<'f, F> {
+    /// Shapes a run of text set in `style`, using the text font (cf [`FontContext::with_text_font`]) and, for characters it lacks, the math font.  
+    /// The text font being a single face, `style` only applies to characters taken from the math font.
+    pub fn shape_text(&self, text: &str, style: Style) -> Result<Vec<ShapedGlyph<'f, F>>, FontError> {
+        let mut shaped = Vec::new();
+        let mut run = Vec::new();
+        let mut run_font = self.font;
+
+        for character in text.chars() {
+            if character.is_ascii_whitespace() {
+                self.shape_run(run_font, &run, &mut shaped)?;
+                run.clear();
+                shaped.push(self.space());
+                continue;
+            }
+
+            // A combining mark stays in the font of its base when possible, so that it can be placed with the anchors of the font
+            let (font, character) = match is_combining_mark(character) {
+                true if !run.is_empty() && run_font.glyph_index(character).is_some() => (run_font, character),
+                _ => self.styled_character(character, style),
+            };
+            if !std::ptr::eq(font, run_font) {
+                self.shape_run(run_font, &run, &mut shaped)?;
+                run.clear();
+                run_font = font;
+            }
+            run.push(character);
+        }
+        self.shape_run(run_font, &run, &mut shaped)?;
+
+        Ok(shaped)
+    }
+
+    /// The font in which a character of text is set, along with the character to look up in that font: 
+    /// the text font if it has the character, the math font otherwise, in which letters and digits are styled
+    fn styled_character(&self, character: char, style: Style) -> (&'f F, char) {
+        match self.text_font {
+            Some(text_font) if text_font.glyph_index(character).is_some() => (text_font, character),
+            // Only letters and digits change with the font ; e.g. a hyphen must not become a minus sign
+            _ if character.is_alphanumeric() => (self.font, style_symbol(character, style)),
+            _ => (self.font, character),
+        }
+    }
+
+    /// A space, as wide as the space glyph of the text font
+    fn space(&self) -> ShapedGlyph<'f, F> {
+        let font = self.text_font_for(' ');
+        let advance = font.glyph_index(' ')
+            .and_then(|gid| font.glyph_from_gid(gid).ok())
+            .map(|glyph| self.length_to_math_units(font, glyph.advance))
+            .unwrap_or(FALLBACK_SPACE_WIDTH * self.units_per_em);
+
+        ShapedGlyph {
+            glyph: None,
+            x_offset: Unit::ZERO,
+            y_offset: Unit::ZERO,
+            advance,
+        }
+    }
+
+    /// Shapes a run of characters which are all set in `font`
+    fn shape_run(&self, font: &'f F, run: &[char], shaped: &mut Vec<ShapedGlyph<'f, F>>) -> Result<(), FontError> {
+        let mut glyph_ids = Vec::with_capacity(run.len());
+        let mut marks = Vec::with_capacity(run.len());
+        for &character in run {
+            let gid = font.glyph_index(character).ok_or(FontError::MissingGlyphCodepoint(character))?;
+            glyph_ids.push(gid);
+            marks.push(is_combining_mark(character) || font.is_mark(gid));
+        }
+
+        // Ligatures
+        let mut index = 0;
+        while index < glyph_ids.len() {
+            if let Some((ligature, length)) = font.ligature(&glyph_ids[index ..]) {
+                glyph_ids.splice(index .. index + length, [ligature]);
+                marks.drain(index + 1 .. index + length);
+            }
+            index += 1;
+        }
+
+        // Positioning
+        let to_math_units = |length: Unit<FUnit>| self.length_to_math_units(font, length);
+        let mut position = Unit::ZERO;
+        let mut base : Option<Base> = None;
+        for (gid, is_mark) in glyph_ids.into_iter().zip(marks) {
+            let glyph = self.to_math_units(font.glyph_from_gid(gid)?);
+
+            if let (true, Some(base)) = (is_mark, base.as_ref()) {
+                let (x_offset, y_offset) = match font.mark_anchor(base.gid, gid) {
+                    Some((x, y)) => (base.position + to_math_units(x) - position, to_math_units(y)),
+                    // Without anchors, the mark is centered over the base and raised by as much as the base exceeds the height the mark is designed for
+                    None => {
+                        let mark_center = (glyph.bbox.0 + glyph.bbox.2).scale(0.5);
+                        let x_offset = base.position + base.advance.scale(0.5) - mark_center - position;
+                        let accent_base_height = self.constants.accent_base_height * self.units_per_em;
+                        let y_offset =
+                            if glyph.bbox.1 >= Unit::ZERO { (base.height - accent_base_height).max(Unit::ZERO) }
+                            else { Unit::ZERO };
+                        (x_offset, y_offset)
+                    },
+                };
+                shaped.push(ShapedGlyph {
+                    glyph: Some(glyph),
+                    x_offset,
+                    y_offset,
+                    advance: Unit::ZERO,
+                });
+                continue;
+            }
+
+            if let Some(base) = base.as_ref() {
+                if let Some(kerning) = font.pair_kerning(base.gid, gid) {
+                    let kerning = to_math_units(kerning);
+                    shaped[base.index].advance += kerning;
+                    position += kerning;
+                }
+            }
+
+            base = Some(Base {
+                gid,
+                index: shaped.len(),
+                position,
+                advance: glyph.advance,
+                height: glyph.height(),
+            });
+            position += glyph.advance;
+            shaped.push(ShapedGlyph {
+                advance: glyph.advance,
+                glyph: Some(glyph),
+                x_offset: Unit::ZERO,
+                y_offset: Unit::ZERO,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Whether the character is a combining diacritical mark (e.g. U+0301 COMBINING ACUTE ACCENT)
+fn is_combining_mark(character: char) -> bool {
+    matches!(character,
+          '\u{0300}' ..= '\u{036F}'
+        | '\u{1AB0}' ..= '\u{1AFF}'
+        | '\u{1DC0}' ..= '\u{1DFF}'
+        | '\u{20D0}' ..= '\u{20FF}'
+        | '\u{FE20}' ..= '\u{FE2F}'
+    )
+}
@@ -1,4 +1,4 @@
-use super::Style;
+use super::{Family, Style, Weight};
 
 // BMP codepoint points of each symbol group.
 const UPPER_A: u32 = 0x41;
@@ -72,6 +72,17 @@ const DIGIT_LUT: [u32; 28] = [
     48      - DIGIT_0, 48      - DIGIT_0, 0x1D7CE - DIGIT_0, 0x1D7CE - DIGIT_0,   // Normal
 ];
 
+/// Whether the codepoint has bold counterparts among the mathematical alphanumeric symbols,
+/// i.e. whether it is a Latin letter, a Greek letter, a digit, `∇`, `∂` or a variant Greek letter (e.g. `ϵ`)
+pub fn has_bold_variant(codepoint: char) -> bool {
+    match codepoint as u32 {
+        LOWER_A ..= LOWER_Z | UPPER_A ..= UPPER_Z 
+        | UPPER_ALPHA ..= UPPER_OMEGA | LOWER_ALPHA ..= LOWER_OMEGA 
+        | DIGIT_0 ..= DIGIT_9 => true,
+        sym => bold_greek_offset(sym).is_some(),
+    }
+}
+
 /// Take a codepoint and a style (a weight and family pair), and remaps a given codepoint  
 /// to the appropriate mathematical codepoint one in the Unicode table (e.g. in roman italic, maps the "vanilla" 'U+0051 Latin Small Letter A' to 'U+1D44E Mathematical Italic Small A')
 pub fn style_symbol(codepoint: char, style: Style) -> char {
@@ -104,31 +115,54 @@ fn style_lookup(lut: &[u32], codepoint: u32, style: Style) -> u32 {
 //   codepoint-math-usv.dtx in codepoint-math
 //   RenderMathMLToken.cpp in WebKit
 //
-// const VAR_UPPER_THETA:      u32 = 0x3F4;
 // const VAR_DIGAMMA:          u32 = 0x3DC;  ??
-// const VAR_EPSILON:          u32 = 0x3F5;
-// const VAR_THETA:            u32 = 0x3D1;
-// const VAR_KAPPA:            u32 = 0x3F0;
-// const VAR_PHI:              u32 = 0x3D5;
-// const VAR_RHO:              u32 = 0x3F1;
-// const VAR_PI:               u32 = 0x3D6;
 // const DIGAMMA:              u32 = 0x3DD; ??
-// const PARTIAL_DIFFERENTIAL: u32 = 0x2202;
-// const NABLA:                u32 = 0x2207;
+//
+// Only their bold variants are handled for now (for `\boldsymbol{..}`): they are laid out 
+// after the Greek letters in each mathematical alphanumeric block.
+const VAR_UPPER_THETA:      u32 = 0x3F4;
+const VAR_EPSILON:          u32 = 0x3F5;
+const VAR_THETA:            u32 = 0x3D1;
+const VAR_KAPPA:            u32 = 0x3F0;
+const VAR_PHI:              u32 = 0x3D5;
+const VAR_RHO:              u32 = 0x3F1;
+const VAR_PI:               u32 = 0x3D6;
+const PARTIAL_DIFFERENTIAL: u32 = 0x2202;
+const NABLA:                u32 = 0x2207;
 
-// const BOLD_VAR_UPPER_THETA:      u32 = 0x1D6B9;
-// const BOLD_VAR_EPSILON:          u32 = 0x1D6DC;
-// const BOLD_VAR_THETA:            u32 = 0x1D6DD;
-// const BOLD_VAR_KAPPA:            u32 = 0x1D6DE;
-// const BOLD_VAR_PHI:              u32 = 0x1D6DF;
-// const BOLD_VAR_RHO:              u32 = 0x1D6E0;
-// const BOLD_VAR_PI:               u32 = 0x1D6E1;
-// const BOLD_NABLA:                u32 = 0x1D6C1;
-// const BOLD_PARTIAL_DIFFERENTIAL: u32 = 0x1D6DB;
+/// Position of the symbol in a block of Greek letters of the mathematical alphanumeric symbols, 
+/// either relative to the uppercase alpha (`true`) or to the lowercase alpha (`false`)
+fn bold_greek_offset(sym: u32) -> Option<(bool, u32)> {
+    Some(match sym {
+        VAR_UPPER_THETA      => (true,  0x11),
+        NABLA                => (true,  0x19),
+        PARTIAL_DIFFERENTIAL => (false, 0x19),
+        VAR_EPSILON          => (false, 0x1A),
+        VAR_THETA            => (false, 0x1B),
+        VAR_KAPPA            => (false, 0x1C),
+        VAR_PHI              => (false, 0x1D),
+        VAR_RHO              => (false, 0x1E),
+        VAR_PI               => (false, 0x1F),
+        _ => return None,
+    })
+}
+
+fn style_other(sym: u32, style: Style) -> u32 {
+    if sym == 0x2D {
+        return 0x2212; // Minus sign
+    }
 
-fn style_other(sym: u32, _: Style) -> u32 {
-    match sym {
-        0x2D => 0x2212, // Minus sign
-        _ => sym,
+    // The uppercase and lowercase alpha of the bold blocks
+    let (upper_alpha, lower_alpha) = match (style.family, style.weight) {
+        (Family::SansSerif, Weight::Bold)       => (0x1D756, 0x1D770),
+        (Family::SansSerif, Weight::BoldItalic) => (0x1D790, 0x1D7AA),
+        (_, Weight::Bold)       => (0x1D6A8, 0x1D6C2),
+        (_, Weight::BoldItalic) => (0x1D71C, 0x1D736),
+        _ => return sym,
+    };
+    match bold_greek_offset(sym) {
+        Some((true,  offset)) => upper_alpha + offset,
+        Some((false, offset)) => lower_alpha + offset,
+        None => sym,
     }
 }
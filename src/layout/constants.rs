@@ -18,6 +18,26 @@ pub const BASELINE_SKIP : Unit<Em> = Unit::<Em>::new(1.2);
 
 
 
+// The default values of `\fboxsep` and `\fboxrule` are 3pt and 0.4pt ; as for `BIG_HEIGHT`, they are expressed relative to a 10pt font size.
+/// Space between the contents of `\boxed{..}`, `\fbox{..}`, etc. and their frame (`\fboxsep`).
+pub const FBOX_SEP  : Unit<Em> = Unit::<Em>::new(0.3);
+/// Thickness of the frame drawn by `\boxed{..}`, `\fbox{..}`, etc. (`\fboxrule`).
+pub const FBOX_RULE : Unit<Em> = Unit::<Em>::new(0.04);
+
+// The values below are chosen to resemble the output of the "cancel" package
+/// How far the arrow of `\cancelto{..}{..}` extends horizontally beyond the struck out nodes.
+pub const CANCELTO_ARROW_OVERHANG : Unit<Em> = Unit::<Em>::new(0.25);
+/// Length of each of the two strokes forming the head of the arrow of `\cancelto{..}{..}`.
+pub const CANCELTO_ARROW_HEAD     : Unit<Em> = Unit::<Em>::new(0.25);
+
+// In amsbsy, `\pmb` overstrikes a copy shifted .025em to the left, one shifted .025em to the right and one raised by .0433em
+/// Horizontal distance between the overstruck copies of `\pmb{..}`.
+pub const PMB_OFFSET : Unit<Em> = Unit::<Em>::new(0.05);
+
+/// Extra width given to extensible arrows (e.g. `\xrightarrow{..}`) on top of the width of their labels.
+// In amsmath, `\ext@arrow` pads the labels with 5mu and 9mu on each side (i.e. 14mu in total).
+pub const EXTENSIBLE_ARROW_PADDING : Unit<Em> = Unit::<Em>::new(14. / 18.);
+
 
 // Values obtained by writing `\the\lineskiplimit` and `\the\lineskip` in an array environment
 /// If the distance between the depth of a line (how far below the baseline the text on that line goes) and the height of the following line (how far above the baseline the text on that line goes)
@@ -48,3 +68,10 @@ pub const RULE_WIDTH        : Unit<Pt> = Unit::<Pt>::new(0.4) ;  // \arrayrulewi
 
 /// Space between two consecutive vertical bars in an array (e.g. `\begin{array}{c||c} .. \end{array}`)
 pub const DOUBLE_RULE_SEP   : Unit<Pt> = Unit::<Pt>::new(2.0) ;  // \doublerulesep
+
+// The values below are the default values from the "arydshln" package
+/// Length of a dash in a dashed line of an array (e.g. `\hdashline`)
+pub const DASH_LINE_DASH    : Unit<Pt> = Unit::<Pt>::new(4.0) ;  // \dashlinedash
+
+/// Length of the gap between two dashes in a dashed line of an array (e.g. `\hdashline`)
+pub const DASH_LINE_GAP     : Unit<Pt> = Unit::<Pt>::new(4.0) ;  // \dashlinegap
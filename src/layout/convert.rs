@@ -1,7 +1,7 @@
 //! This is a collection of tools used for converting ParseNodes into LayoutNodes.
 
 use crate::font::{Glyph, Direction, VariantGlyph, MathFont};
-use crate::dimensions::{Unit, AnyUnit};
+use crate::dimensions::{Unit, AnyUnit, RelativeUnit};
 use crate::dimensions::units::{Px, Em, FUnit};
 use crate::layout::LayoutSettings;
 
@@ -137,9 +137,13 @@ impl Scaled for Unit<Em> {
 }
 impl Scaled for AnyUnit {
     fn scaled<F>(self, config: LayoutSettings<F>) -> Unit<Px> {
-        let length = match self {
-            AnyUnit::Em(em) => Unit::<Em>::new(em) * config.font_size,
-            AnyUnit::Px(px) => Unit::<Px>::new(px)
+        let length = match self.split_physical() {
+            // As in TeX, physical units are not affected by the current style
+            Ok(physical) => return physical,
+            Err(RelativeUnit::Em(em)) => Unit::<Em>::new(em) * config.font_size,
+            Err(RelativeUnit::Ex(ex)) => config.ctx.constants.x_height.scale(ex) * config.font_size,
+            Err(RelativeUnit::Mu(mu)) => Unit::<Em>::new(mu / 18.) * config.font_size,
+            Err(RelativeUnit::Px(px)) => Unit::<Px>::new(px),
         };
         length.scale(config.scale_factor())
     }
@@ -8,18 +8,19 @@ use std::unimplemented;
 
 use super::builders;
 use super::convert::AsLayoutNode;
-use super::{Alignment, Layout, LayoutNode, LayoutSettings, LayoutVariant, Style};
+use super::{Alignment, ColorChange, Layout, LayoutNode, LayoutSettings, LayoutVariant, LineSegment, Style};
 
 use crate::font::MathFont;
 use crate::font::{
-    kerning::{superscript_kern, subscript_kern},
+    kerning::{superscript_kern, subscript_kern, presuperscript_kern, presubscript_kern},
     VariantGlyph,
-    TexSymbolType
+    TexSymbolType,
+    Style as FontStyle,
 };
-use crate::layout::constants::{BASELINE_SKIP, COLUMN_SEP, DOUBLE_RULE_SEP, JOT, LINE_SKIP_ARRAY, LINE_SKIP_LIMIT_ARRAY, RULE_WIDTH, STRUT_DEPTH, STRUT_HEIGHT};
+use crate::layout::constants::{BASELINE_SKIP, CANCELTO_ARROW_HEAD, CANCELTO_ARROW_OVERHANG, COLUMN_SEP, DASH_LINE_DASH, DASH_LINE_GAP, DOUBLE_RULE_SEP, EXTENSIBLE_ARROW_PADDING, JOT, LINE_SKIP_ARRAY, LINE_SKIP_LIMIT_ARRAY, PMB_OFFSET, RULE_WIDTH, STRUT_DEPTH, STRUT_HEIGHT};
 use super::convert::Scaled;
 use super::spacing::{atom_space, Spacing};
-use crate::parser::nodes::{Accent, Array, ArrayColumnAlign, BarThickness, ColSeparator, Delimited, ExtendedDelimiter, FontEffect, GenFraction, MathStyle, ParseNode, PlainText, Radical, Scripts, Stack};
+use crate::parser::nodes::{Accent, Array, ArrayColumnAlign, BarThickness, BoxWidth, Cancel, CancelKind, ColSeparator, Delimited, ExtendedDelimiter, ExtensibleArrow, FontEffect, Framed, GenFraction, MathStyle, OverUnder, ParseNode, PlainText, PoorMansBold, Radical, ResizedBox, RowSeparator, Scripts, Stack};
 use crate::parser::symbols::Symbol;
 use crate::dimensions::Unit;
 use crate::dimensions::units::Px;
@@ -137,6 +138,8 @@ impl<'f, F : MathFont> Layout<'f, F> {
             ParseNode::Delimited(ref delim) => self.delimited(delim, config)?,
             ParseNode::ExtendedDelimiter(ref delim) => self.extended_delimiter(delim, config)?,
             ParseNode::Accent(ref acc) => self.accent(acc, config)?,
+            ParseNode::OverUnder(ref ou) => self.over_under(ou, config)?,
+            ParseNode::ExtensibleArrow(ref arrow) => self.extensible_arrow(arrow, config)?,
             ParseNode::GenFraction(ref f) => self.frac(f, config)?,
             ParseNode::Stack(ref stack) => self.substack(stack, config)?,
             ParseNode::Array(ref arr) => self.array(arr, config)?,
@@ -145,25 +148,31 @@ impl<'f, F : MathFont> Layout<'f, F> {
             ParseNode::Group(ref gp) => self.add_node(layout(gp, config)?.as_node()),
             ParseNode::Rule(rule) => self.add_node(rule.as_layout(config)?),
             ParseNode::Kerning(kern) => self.add_node(kern!(horz: kern.scaled(config))),
+            ParseNode::StyledKerning(kern) => {
+                let kern = if config.style >= Style::DisplayCramped { kern.display }
+                    else if config.style >= Style::TextCramped { kern.text }
+                    else { kern.script };
+                self.add_node(kern!(horz: kern.scaled(config)))
+            },
+            ParseNode::MathChoice(ref choice) => self.add_node(layout(choice.choose(config.style), config)?.as_node()),
 
             ParseNode::Color(ref clr) => {
                 let inner = layout_recurse(&clr.inner, config, next)?;
                 self.add_node(builders::color(inner, clr))
             }
+            ParseNode::PoorMansBold(PoorMansBold { ref inner }) => {
+                let inner = layout_recurse(inner, config, next)?;
+                self.poor_mans_bold(inner, config)
+            },
+
+            ParseNode::Framed(ref framed) => self.framed(framed, config)?,
+            ParseNode::Cancel(ref cancel) => self.cancel(cancel, config)?,
+            ParseNode::ResizedBox(ref resized) => self.resized_box(resized, config)?,
 
             ParseNode::DummyNode(_) => (),
             ParseNode::FontEffect(FontEffect { inner: ref children }) => self.underline(layout(children, config)?.as_node(), config),
 
-            ParseNode::PlainText(PlainText {ref text}) => {
-                for character in text.chars() {
-                    if character.is_ascii_whitespace() {
-                        self.add_node(kern![horz : Spacing::Medium.to_length().scaled(config)])
-                    }
-                    else {
-                        self.add_node(config.ctx.glyph(character)?.as_layout(config)?);
-                    }
-                }
-            },
+            ParseNode::PlainText(PlainText {ref text, style}) => self.plain_text(text, style, config)?,
 
             // TODO: understand whether this is needed anywhere
             ParseNode::Style(_)     => unimplemented!(),
@@ -180,6 +189,38 @@ impl<'f, F : MathFont> Layout<'f, F> {
         }
     }
 
+    /// Adds a run of text, shaped with the text font (cf [`FontContext::shape_text`](crate::font::FontContext::shape_text))
+    fn plain_text<'a>(&mut self, text: &str, style: FontStyle, config: LayoutSettings<'a, 'f, F>) -> LayoutResult<()> {
+        for shaped in config.ctx.shape_text(text, style)? {
+            let advance = shaped.advance.scaled(config);
+            let glyph = match shaped.glyph {
+                Some(glyph) => glyph,
+                // a space
+                None => {
+                    self.add_node(kern![horz : advance]);
+                    continue;
+                },
+            };
+
+            let mut node = glyph.as_layout(config)?;
+            node.width = advance;
+            if !shaped.y_offset.is_zero() {
+                node = vbox!(offset: -shaped.y_offset.scaled(config); node);
+            }
+
+            if shaped.x_offset.is_zero() {
+                self.add_node(node);
+            }
+            else {
+                let x_offset = shaped.x_offset.scaled(config);
+                self.add_node(kern![horz : x_offset]);
+                self.add_node(node);
+                self.add_node(kern![horz : -x_offset]);
+            }
+        }
+        Ok(())
+    }
+
     /// Adds an underline below a node
     fn underline<'a>(&mut self, node: LayoutNode<'f, F>, config : LayoutSettings<'a, 'f, F>) {
         let width = node.width;
@@ -387,10 +428,22 @@ impl<'f, F : MathFont> Layout<'f, F> {
             None => Layout::new(),
         };
 
+        let mut presup = match scripts.presuperscript {
+            Some(ref sup) => layout(sup, config.superscript_variant())?,
+            None => Layout::new(),
+        };
+
+        let mut presub = match scripts.presubscript {
+            Some(ref sub) => layout(sub, config.subscript_variant())?,
+            None => Layout::new(),
+        };
+
+        let has_prescripts = scripts.presuperscript.is_some() || scripts.presubscript.is_some();
+
         // We use a different algoirthm for handling scripts for operators with limits.
         // This is where he handle Operators with limits.
         if let Some(ref b) = scripts.base {
-            if TexSymbolType::Operator(true) == b.atom_type() {
+            if TexSymbolType::Operator(true) == b.atom_type() && !has_prescripts {
                 self.operator_limits(base, sup, sub, config)?;
                 return Ok(());
             }
@@ -402,8 +455,25 @@ impl<'f, F : MathFont> Layout<'f, F> {
         let mut adjust_down = Unit::ZERO;
         let mut sup_kern = Unit::ZERO;
         let mut sub_kern = Unit::ZERO;
+        let mut presup_kern = Unit::ZERO;
+        let mut presub_kern = Unit::ZERO;
+
+        // Scripts on both sides of the base are shifted by the same amount ; 
+        // the lowest superscript and the highest subscript determine these shifts
+        let has_sup = scripts.superscript.is_some() || scripts.presuperscript.is_some();
+        let has_sub = scripts.subscript.is_some() || scripts.presubscript.is_some();
+        let sup_depth = match (&scripts.superscript, &scripts.presuperscript) {
+            (Some(_), Some(_)) => Unit::min(sup.depth, presup.depth),
+            (None, Some(_))    => presup.depth,
+            _                  => sup.depth,
+        };
+        let sub_height = match (&scripts.subscript, &scripts.presubscript) {
+            (Some(_), Some(_)) => Unit::max(sub.height, presub.height),
+            (None, Some(_))    => presub.height,
+            _                  => sub.height,
+        };
 
-        if scripts.superscript.is_some() {
+        if has_sup {
             // Use default font values for first iteration of vertical height.
             adjust_up = match config.style.is_cramped() {
                 true => config.ctx.constants.superscript_shift_up_cramped,
@@ -434,6 +504,11 @@ impl<'f, F : MathFont> Layout<'f, F> {
                         } else {
                             sup_kern = base_sym.italics;
                         }
+                        if let Some(presup_sym) = presup.is_symbol() {
+                            let bg = config.ctx.glyph_from_gid(base_sym.gid)?;
+                            let sg = config.ctx.glyph_from_gid(presup_sym.gid)?;
+                            presup_kern = presuperscript_kern(&bg, &sg, config.to_font(adjust_up)).scaled(config);
+                        }
                     }
                 }
             }
@@ -441,15 +516,15 @@ impl<'f, F : MathFont> Layout<'f, F> {
             let drop_max = config.ctx.constants.superscript_baseline_drop_max.scaled(config);
             adjust_up = max!(adjust_up,
                             height - drop_max,
-                            config.ctx.constants.superscript_bottom_min.scaled(config) - sup.depth);
+                            config.ctx.constants.superscript_bottom_min.scaled(config) - sup_depth);
         }
 
         // We calculate the vertical position of the subscripts.  The `adjust_down`
         // variable will describe how far we need to adjust the subscript down.
-        if scripts.subscript.is_some() {
+        if has_sub {
             // Use default font values for first iteration of vertical height.
             adjust_down = max!(config.ctx.constants.subscript_shift_down.scaled(config),
-                                sub.height - config.ctx.constants.subscript_top_max.scaled(config),
+                                sub_height - config.ctx.constants.subscript_top_max.scaled(config),
                                 config.ctx.constants.subscript_baseline_drop_min.scaled(config) - base.depth);
 
             // Provided that the base and subscript are symbols, we apply
@@ -468,13 +543,19 @@ impl<'f, F : MathFont> Layout<'f, F> {
                     let sg = config.ctx.glyph_from_gid(ssym.gid)?;
                     sub_kern += subscript_kern(&bg, &sg, config.to_font(adjust_down)).scaled(config);
                 }
+
+                if let (Some(ssym), Some(bsym)) = (presub.is_symbol(), base.is_symbol()) {
+                    let bg = config.ctx.glyph_from_gid(bsym.gid)?;
+                    let sg = config.ctx.glyph_from_gid(ssym.gid)?;
+                    presub_kern = presubscript_kern(&bg, &sg, config.to_font(adjust_down)).scaled(config);
+                }
             }
         }
 
         // TODO: lazy gap fix; see BottomMaxWithSubscript
-        if scripts.subscript.is_some() && scripts.superscript.is_some() {
-            let sup_bot = adjust_up + sup.depth;
-            let sub_top = sub.height - adjust_down;
+        if has_sub && has_sup {
+            let sup_bot = adjust_up + sup_depth;
+            let sub_top = sub_height - adjust_down;
             let gap_min = config.ctx.constants.sub_superscript_gap_min.scaled(config);
             if sup_bot - sub_top < gap_min {
                 let adjust = (gap_min - sup_bot + sub_top).scale(0.5);
@@ -483,29 +564,42 @@ impl<'f, F : MathFont> Layout<'f, F> {
             }
         }
 
-        let mut contents = builders::VBox::new();
-        if scripts.superscript.is_some() {
-            if !sup_kern.is_zero() {
-                sup.contents.insert(0, kern!(horz: sup_kern));
-                sup.width += sup_kern;
+        if has_prescripts {
+            // Pre-scripts are right-aligned against the base, the kerning is thus applied on their right
+            if !presup_kern.is_zero() {
+                presup.contents.push(kern!(horz: presup_kern));
+                presup.width += presup_kern;
             }
-
-            let corrected_adjust = adjust_up - sub.height + adjust_down;
-            contents.add_node(sup.as_node());
-            contents.add_node(kern!(vert: corrected_adjust));
+            if !presub_kern.is_zero() {
+                presub.contents.push(kern!(horz: presub_kern));
+                presub.width += presub_kern;
+            }
+            let width = Unit::max(presup.width, presub.width);
+            presup.alignment = Alignment::Right(presup.width);
+            presup.width = width;
+            presub.alignment = Alignment::Right(presub.width);
+            presub.width = width;
+
+            let presup = scripts.presuperscript.as_ref().map(|_| presup);
+            let presub = scripts.presubscript.as_ref().map(|_| presub);
+            self.add_node(script_column(presup, presub, adjust_up, adjust_down));
         }
 
-        contents.set_offset(adjust_down);
-        if scripts.subscript.is_some() { 
-            if !sub_kern.is_zero() {
-                sub.contents.insert(0, kern!(horz: sub_kern));
-                sub.width += sub_kern;
-            }
-            contents.add_node(sub.as_node());
+        if scripts.superscript.is_some() && !sup_kern.is_zero() {
+            sup.contents.insert(0, kern!(horz: sup_kern));
+            sup.width += sup_kern;
+        }
+        if scripts.subscript.is_some() && !sub_kern.is_zero() {
+            sub.contents.insert(0, kern!(horz: sub_kern));
+            sub.width += sub_kern;
         }
 
         self.add_node(base.as_node());
-        self.add_node(contents.build());
+        if scripts.superscript.is_some() || scripts.subscript.is_some() {
+            let sup = scripts.superscript.as_ref().map(|_| sup);
+            let sub = scripts.subscript.as_ref().map(|_| sub);
+            self.add_node(script_column(sup, sub, adjust_up, adjust_down));
+        }
 
         Ok(())
     }
@@ -559,11 +653,256 @@ impl<'f, F : MathFont> Layout<'f, F> {
         Ok(())
     }
 
+    fn over_under<'a>(&mut self, over_under: &OverUnder, config: LayoutSettings<'a, 'f, F>) -> Result<(), LayoutError> {
+        let base = layout(&over_under.base, config)?;
+        let over = match over_under.over {
+            Some(ref over) => Some(layout(over, config.superscript_variant())?),
+            None => None,
+        };
+        let under = match over_under.under {
+            Some(ref under) => Some(layout(under, config.subscript_variant())?),
+            None => None,
+        };
+
+        self.stack_labels(base, over, under, over_under.stretchy, config);
+        Ok(())
+    }
+
+    fn extensible_arrow<'a>(&mut self, arrow: &ExtensibleArrow, config: LayoutSettings<'a, 'f, F>) -> Result<(), LayoutError> {
+        // An empty label (e.g. `\xrightarrow{}`) is treated as no label at all
+        let over = if arrow.over.is_empty() {
+            None
+        } else {
+            Some(layout(&arrow.over, config.superscript_variant())?)
+        };
+        let under = match arrow.under {
+            Some(ref under) if !under.is_empty() => Some(layout(under, config.subscript_variant())?),
+            _ => None,
+        };
+
+        let labels_width = Unit::max(
+            over.as_ref().map(|over| over.width).unwrap_or(Unit::ZERO),
+            under.as_ref().map(|under| under.width).unwrap_or(Unit::ZERO)
+        );
+        let width = labels_width + EXTENSIBLE_ARROW_PADDING.scaled(config);
+        let glyph = config.ctx.horz_variant(arrow.arrow, config.to_font(width))?.as_layout(config)?;
+
+        let mut base = Layout::new();
+        base.add_node(glyph);
+        self.stack_labels(base.finalize(), over, under, true, config);
+        Ok(())
+    }
+
+    /// Centers `base` and its optional labels on top of each other, keeping the baseline of `base`.
+    /// When `stretchy` is set, the labels are placed as for a stretched construction (e.g. `\overbrace{..}`), otherwise as limits.
+    fn stack_labels<'a>(&mut self, base: Layout<'f, F>, over: Option<Layout<'f, F>>, under: Option<Layout<'f, F>>, stretchy: bool, config: LayoutSettings<'a, 'f, F>) {
+        let width = max!(
+            base.width,
+            over.as_ref().map(|over| over.width).unwrap_or(Unit::ZERO),
+            under.as_ref().map(|under| under.width).unwrap_or(Unit::ZERO)
+        );
+
+        let constants = &config.ctx.constants;
+        let mut vbox = builders::VBox::new();
+
+        if let Some(over) = over {
+            // Distance between the top of the base and the baseline of the label above
+            let over_kern = if stretchy {
+                Unit::max(constants.stretch_stack_top_shift_up.scaled(config) - base.height,
+                    constants.stretch_stack_gap_above_min.scaled(config) - over.depth)
+            } else {
+                Unit::max(constants.upper_limit_baseline_rise_min.scaled(config),
+                    constants.upper_limit_gap_min.scaled(config) - over.depth)
+            };
+            vbox.add_node(over.centered(width).as_node());
+            vbox.add_node(kern!(vert: over_kern));
+        }
+
+        let base_depth = base.depth;
+        vbox.add_node(base.centered(width).as_node());
+
+        if let Some(under) = under {
+            // Distance between the baseline of the base and the top of the label below
+            let under_kern = if stretchy {
+                Unit::max(constants.stretch_stack_bottom_shift_down.scaled(config) - under.height,
+                    constants.stretch_stack_gap_below_min.scaled(config) - base_depth)
+            } else {
+                Unit::max(constants.lower_limit_gap_min.scaled(config),
+                    constants.lower_limit_baseline_drop_min.scaled(config) - under.height) - base_depth
+            };
+            // The baseline of the construction is the baseline of the base
+            vbox.set_offset(under_kern + under.height);
+            vbox.add_node(kern!(vert: under_kern));
+            vbox.add_node(under.centered(width).as_node());
+        }
+
+        self.add_node(vbox.build());
+    }
+
+    fn framed<'a>(&mut self, framed: &Framed, config: LayoutSettings<'a, 'f, F>) -> LayoutResult<()> {
+        let inner = layout(&framed.inner, config)?;
+        let padding   = framed.padding.scaled(config);
+        let thickness = framed.border_thickness.scaled(config);
+        let margin = padding + thickness;
+
+        let width  = inner.width  + margin.scale(2.0);
+        let height = inner.height + margin;
+        let depth  = inner.depth  - margin;
+
+        // The background and the frame come first so that they are drawn beneath the inner nodes
+        let mut hbox = builders::HBox::new();
+        if let Some(fill_color) = framed.fill_color {
+            hbox.add_node(LayoutNode {
+                width,
+                height,
+                depth,
+                node: LayoutVariant::FilledRect(fill_color),
+            });
+            hbox.add_node(kern!(horz: -width));
+        }
+
+        if thickness > Unit::ZERO {
+            let side_height = height - depth - thickness.scale(2.0);
+            let mut frame = builders::VBox::new();
+            frame.add_node(rule!(width: width, height: thickness));
+            frame.add_node(hbox![
+                rule!(width: thickness, height: side_height),
+                kern!(horz: width - thickness.scale(2.0)),
+                rule!(width: thickness, height: side_height)
+            ]);
+            frame.add_node(rule!(width: width, height: thickness));
+            frame.set_offset(-depth);
+            let frame = frame.build();
+
+            match framed.border_color {
+                Some(color) => hbox.add_node(LayoutNode {
+                    width,
+                    height,
+                    depth,
+                    node: LayoutVariant::Color(ColorChange { color, inner: vec![frame] }),
+                }),
+                None => hbox.add_node(frame),
+            }
+            hbox.add_node(kern!(horz: -width));
+        }
+
+        hbox.add_node(kern!(horz: margin));
+        hbox.add_node(inner.as_node());
+        hbox.add_node(kern!(horz: margin));
+
+        self.add_node(hbox.build());
+        Ok(())
+    }
+
+    fn resized_box<'a>(&mut self, resized: &ResizedBox, config: LayoutSettings<'a, 'f, F>) -> LayoutResult<()> {
+        let inner = layout(&resized.inner, config)?;
+        let (width, height, depth) = (inner.width, inner.height, inner.depth);
+
+        // Invisible boxes are empty ; only their dimensions matter
+        let mut hbox = builders::HBox::new();
+        if resized.visible {
+            match resized.width {
+                BoxWidth::Natural => hbox.add_node(inner.as_node()),
+                BoxWidth::OverlapLeft => {
+                    hbox.add_node(kern!(horz: -width));
+                    hbox.add_node(inner.as_node());
+                },
+                BoxWidth::OverlapRight => {
+                    hbox.add_node(inner.as_node());
+                    hbox.add_node(kern!(horz: -width));
+                },
+                BoxWidth::OverlapCenter => {
+                    hbox.add_node(kern!(horz: -width.scale(0.5)));
+                    hbox.add_node(inner.as_node());
+                    hbox.add_node(kern!(horz: -width.scale(0.5)));
+                },
+            }
+        }
+
+        let mut node = hbox.build();
+        node.width  = if let BoxWidth::Natural = resized.width { width } else { Unit::ZERO };
+        node.height = if resized.keep_height { height } else { Unit::ZERO };
+        node.depth  = if resized.keep_depth  { depth }  else { Unit::ZERO };
+        self.add_node(node);
+        Ok(())
+    }
+
+    /// Emboldens nodes by overstriking three copies of them, like `\pmb{..}` in amsbsy: 
+    /// two copies are shifted to the left and to the right, and the third one is raised.
+    fn poor_mans_bold<'a>(&mut self, inner: Layout<'f, F>, config: LayoutSettings<'a, 'f, F>) {
+        let offset = PMB_OFFSET.scaled(config);
+        let inner = inner.as_node();
+        let width = inner.width;
+
+        let mut hbox = builders::HBox::new();
+        hbox.add_node(kern!(horz: -offset.scale(0.5)));
+        hbox.add_node(inner.clone());
+        hbox.add_node(kern!(horz: offset - width));
+        hbox.add_node(inner.clone());
+        hbox.add_node(kern!(horz: -width - offset.scale(0.5)));
+        hbox.add_node(vbox![offset: -offset.scale(0.866); inner]);
+        self.add_node(hbox.build());
+    }
+
+    fn cancel<'a>(&mut self, cancel: &Cancel, config: LayoutSettings<'a, 'f, F>) -> LayoutResult<()> {
+        let inner = layout(&cancel.inner, config)?;
+        let thickness = config.ctx.constants.fraction_rule_thickness.scaled(config);
+        let (width, height, depth) = (inner.width, inner.height, inner.depth);
+
+        // Strokes have zero width and are drawn across the box of the inner nodes which follows them
+        let mut hbox = builders::HBox::new();
+        match (cancel.kind, &cancel.to) {
+            (CancelKind::Forward, Some(to)) => {
+                // The arrow keeps going past the top right corner of the box in the same direction
+                let end_x = width + CANCELTO_ARROW_OVERHANG.scaled(config);
+                let end_y = if width.is_zero() {
+                    height + CANCELTO_ARROW_OVERHANG.scaled(config)
+                } else {
+                    depth + (height - depth).scale(end_x.to_unitless() / width.to_unitless())
+                };
+                hbox.add_node(line_segment((Unit::ZERO, depth), (end_x, end_y), thickness));
+
+                // The two strokes of the arrow head make an angle of 30° with the arrow
+                let (dx, dy) = (end_x.to_unitless(), (end_y - depth).to_unitless());
+                let length = dx.hypot(dy);
+                let head = CANCELTO_ARROW_HEAD.scaled(config).to_unitless() / length;
+                for angle in [std::f64::consts::FRAC_PI_6, -std::f64::consts::FRAC_PI_6] {
+                    let (sin, cos) = angle.sin_cos();
+                    let head_x = end_x - Unit::new(head * (dx * cos - dy * sin));
+                    let head_y = end_y - Unit::new(head * (dx * sin + dy * cos));
+                    hbox.add_node(line_segment((end_x, end_y), (head_x, head_y), thickness));
+                }
+
+                hbox.add_node(inner.as_node());
+
+                // The value sits right above the tip of the arrow
+                let to = layout(to, config.superscript_variant())?;
+                let shift = to.depth - end_y;
+                hbox.add_node(kern!(horz: end_x - width));
+                hbox.add_node(vbox![offset: shift; to.as_node()]);
+            },
+            (kind, _) => {
+                if let CancelKind::Forward | CancelKind::Cross = kind {
+                    hbox.add_node(line_segment((Unit::ZERO, depth), (width, height), thickness));
+                }
+                if let CancelKind::Backward | CancelKind::Cross = kind {
+                    hbox.add_node(line_segment((Unit::ZERO, height), (width, depth), thickness));
+                }
+                hbox.add_node(inner.as_node());
+            },
+        }
+
+        self.add_node(hbox.build());
+        Ok(())
+    }
+
     fn frac<'a>(&mut self, frac: &GenFraction, config: LayoutSettings<'a, 'f, F>) -> Result<(), LayoutError> {
         let config = match frac.style {
             MathStyle::NoChange => config.clone(),
             MathStyle::Display => config.with_display(),
             MathStyle::Text => config.with_text(),
+            MathStyle::Script => config.with_script(),
+            MathStyle::ScriptScript => config.with_scriptscript(),
         };
 
         let bar = match frac.bar_thickness {
@@ -579,7 +918,11 @@ impl<'f, F : MathFont> Layout<'f, F> {
             d.alignment = Alignment::Centered(d.width);
             d.width = n.width;
         } else {
-            n.alignment = Alignment::Centered(n.width);
+            n.alignment = match frac.numerator_align {
+                ArrayColumnAlign::Centered => Alignment::Centered(n.width),
+                ArrayColumnAlign::Left     => Alignment::Left,
+                ArrayColumnAlign::Right    => Alignment::Right(n.width),
+            };
             n.width = d.width;
         }
 
@@ -680,7 +1023,26 @@ impl<'f, F : MathFont> Layout<'f, F> {
         // TODO: This is unclear
         let top_padding = rule_ascender - rule_thickness;
 
-        self.add_node(vbox![offset: offset; sqrt]);
+        let sqrt = vbox![offset: offset; sqrt];
+
+        // the degree is set in scriptscript style, its bottom raised by a percentage of the radical's total height
+        if let Some(ref degree) = rad.degree {
+            let degree = layout(degree, config.superscript_variant().superscript_variant())?.as_node();
+            let kern_after  = config.ctx.constants.radical_kern_after_degree.scaled(config);
+            // the degree must not stick out to the left of the radical
+            let kern_before = Unit::max(
+                config.ctx.constants.radical_kern_before_degree.scaled(config),
+                - (degree.width + kern_after),
+            );
+            let raise_percent = config.ctx.constants.radical_degree_bottom_raise_percent;
+            let raise = (sqrt.height - sqrt.depth).scale(raise_percent) + sqrt.depth;
+
+            self.add_node(kern!(horz: kern_before));
+            self.add_node(vbox![offset: -raise; degree]);
+            self.add_node(kern!(horz: kern_after));
+        }
+
+        self.add_node(sqrt);
         self.add_node(vbox![kern!(vert: top_padding),
                             rule!(width:  contents.width, height: rule_thickness),
                             kern!(vert: gap),
@@ -772,18 +1134,40 @@ impl<'f, F : MathFont> Layout<'f, F> {
 
     fn array<'a>(&mut self, array: &Array, config: LayoutSettings<'a, 'f, F>) -> Result<(), LayoutError> {
         let cell_layout_settings = config.layout_style(array.cell_layout_style);
-        let normal_baseline_skip = BASELINE_SKIP; 
-        let strut_height     = normal_baseline_skip.scale(STRUT_HEIGHT) * config.font_size; 
-        let strut_depth      = - normal_baseline_skip.scale(STRUT_DEPTH)  * config.font_size; 
+        let normal_baseline_skip = match array.row_spacing {
+            Some(row_spacing) => row_spacing.baseline_skip.scaled(config),
+            None => BASELINE_SKIP * config.font_size,
+        };
+        // rows with custom spacing (e.g. `smallmatrix`) are not padded with struts
+        let (strut_height, strut_depth) = match array.row_spacing {
+            Some(_) => (Unit::ZERO, Unit::ZERO),
+            None => (BASELINE_SKIP.scale(STRUT_HEIGHT) * config.font_size, - BASELINE_SKIP.scale(STRUT_DEPTH) * config.font_size),
+        };
 
         let jot = if array.extra_row_sep { JOT } else { Unit::ZERO };
-        let baseline_skip = normal_baseline_skip * config.font_size + jot * Unit::standard_pt_to_px();
-        let line_skip = (LINE_SKIP_ARRAY + jot) * Unit::standard_pt_to_px();
-        let line_skip_limit = (LINE_SKIP_LIMIT_ARRAY + jot)  * Unit::standard_pt_to_px();
+        let baseline_skip = normal_baseline_skip + jot * Unit::standard_pt_to_px();
+        let (line_skip, line_skip_limit) = match array.row_spacing {
+            // as in amsmath's `smallmatrix`, `\lineskiplimit` is set to `\lineskip`
+            Some(row_spacing) => {
+                let line_skip = row_spacing.line_skip.scaled(config) + jot * Unit::standard_pt_to_px();
+                (line_skip, line_skip)
+            },
+            None => (
+                (LINE_SKIP_ARRAY + jot) * Unit::standard_pt_to_px(),
+                (LINE_SKIP_LIMIT_ARRAY + jot)  * Unit::standard_pt_to_px(),
+            ),
+        };
 
-        let half_col_sep     = COLUMN_SEP      * Unit::standard_pt_to_px(); 
-        let rule_width       = RULE_WIDTH      * Unit::standard_pt_to_px();
-        let double_rule_sep  = DOUBLE_RULE_SEP * Unit::standard_pt_to_px();
+        let half_col_sep     = match array.half_col_sep {
+            Some(half_col_sep) => half_col_sep.scaled(config),
+            None => COLUMN_SEP * Unit::standard_pt_to_px(),
+        }; 
+        // the total height of vertical bars is only known once the rows are laid out
+        let mut rule_measurements = RuleMeasurements {
+            rule_width:      RULE_WIDTH      * Unit::standard_pt_to_px(),
+            total_height:    Unit::ZERO,
+            double_rule_sep: DOUBLE_RULE_SEP * Unit::standard_pt_to_px(),
+        };
 
         let null_delimiter_space = config.ctx.constants.null_delimiter_space * config.font_size;
 
@@ -802,7 +1186,8 @@ impl<'f, F : MathFont> Layout<'f, F> {
         // We compute how many columns there are, when taking into account @-expressions
         let all_separators = &array.col_format.separators;
         // We count the number of columns including @-expr columns
-        let num_columns_at = num_columns + all_separators
+        // (rows may have fewer cells than there are columns in the column format)
+        let num_columns_at = array.col_format.alignment.len() + all_separators
             .iter().map(|separators| separators.iter()) 
             .flatten()
             .filter(|separator| matches!(separator, ColSeparator::AtExpression(_)))
@@ -893,9 +1278,20 @@ impl<'f, F : MathFont> Layout<'f, F> {
         debug_assert_eq!(col_widths.len(), num_columns_at);
 
 
+        // Horizontal lines between rows take some vertical space: 
+        // consecutive full lines (`\hline\hline`) are separated by `double_rule_sep`, partial lines (`\cline`) take no space
+        let row_separator_heights : Vec<Unit<Px>> = (0 ..= num_rows)
+            .map(|i_row| {
+                let separators = array.row_separators.get(i_row).map_or(&[] as &[RowSeparator], Vec::as_slice);
+                rule_measurements.row_separators_height(separators)
+            })
+            .collect();
+
         // baseline_dists[0] is dist from top of first line to first baseline (e.g. as though it was preceded by a line of zero-depth)
         // baseline_dists[i] is the dist from row indexed i and row indexed i+1
         let mut baseline_dists = Vec::with_capacity(num_rows);
+        // depth of each row (negative), used to place horizontal lines
+        let mut row_depths = Vec::with_capacity(num_rows);
         let mut prev_depth = Unit::ZERO;
 
         for i_row in 0 .. num_rows {
@@ -921,7 +1317,8 @@ impl<'f, F : MathFont> Layout<'f, F> {
                     baseline_skip
                 }
             ;
-            baseline_dists.push(baseline_dist);
+            baseline_dists.push(baseline_dist + row_separator_heights[i_row]);
+            row_depths.push(max_depth);
             prev_depth = max_depth;
         }
         let last_depth = prev_depth;
@@ -958,7 +1355,7 @@ impl<'f, F : MathFont> Layout<'f, F> {
             }
 
             // add final space to align bottom of boxes
-            let kern = - last_depth;
+            let kern = - last_depth + row_separator_heights[num_rows];
             if kern > Unit::ZERO {
                 vbox.add_node(LayoutNode::vert_kern(kern));
             }
@@ -985,19 +1382,20 @@ impl<'f, F : MathFont> Layout<'f, F> {
         let total_height : Unit<Px> = 
             baseline_dists.iter().cloned().sum::<Unit<Px>>()
             - last_depth
+            + row_separator_heights[num_rows]
         ;
 
-        let rule_measurements = RuleMeasurements {
-            rule_width,
-            total_height,
-            double_rule_sep,
-        };
+        rule_measurements.total_height = total_height;
 
         // add left vertical bars
+        let body_start = hbox.width;
         draw_vertical_bars(&mut hbox, n_vertical_bars[0], rule_measurements);
 
+        // horizontal extent of each column which is not an @-expression, including spaces around it (used to draw `\cline`)
+        let mut column_extents : Vec<(Unit<Px>, Unit<Px>)> = Vec::with_capacity(num_columns);
 
         for (i_col, vbox) in column_vboxes.into_iter().enumerate() {
+            let column_start = hbox.width;
             // insert half col sep before if:
             //   - vbox is not an @-expression
             //   - if this is the first col, there is no left delimiter
@@ -1016,14 +1414,83 @@ impl<'f, F : MathFont> Layout<'f, F> {
                 hbox.add_node(LayoutNode::horiz_kern(half_col_sep));
             }
 
+            if alignments[i_col].is_some() {
+                column_extents.push((column_start, hbox.width));
+            }
+
             // draw vertical bars after
             draw_vertical_bars(&mut hbox, n_vertical_bars[i_col + 1], rule_measurements);
         }
+        let body_end = hbox.width;
 
         if array.right_delimiter.is_none() {
             hbox.add_node(LayoutNode::horiz_kern(null_delimiter_space));
         }
 
+        // draw horizontal lines on top of the body of the array
+        if array.row_separators.iter().any(|separators| !separators.is_empty()) {
+            let mut lines = builders::VBox::new();
+            let mut y = Unit::ZERO;
+            let mut baseline = Unit::ZERO;
+            for (i_row, separators) in array.row_separators.iter().enumerate().take(num_rows + 1) {
+                // lines above a row are placed right below the previous row
+                let top = match i_row {
+                    0 => Unit::ZERO,
+                    _ => baseline - row_depths[i_row - 1],
+                };
+                if let Some(baseline_dist) = baseline_dists.get(i_row) {
+                    baseline += *baseline_dist;
+                }
+
+                let mut line_top = top;
+                let mut prev_full_line = false;
+                for separator in separators {
+                    let (x_start, x_end, dashed) = match *separator {
+                        RowSeparator::HorizontalLine { dashed } => {
+                            if prev_full_line {
+                                line_top += rule_measurements.double_rule_sep;
+                            }
+                            prev_full_line = true;
+                            (body_start, body_end, dashed)
+                        },
+                        RowSeparator::PartialLine { first, last, dashed } => {
+                            prev_full_line = false;
+                            // the parser rejects ranges past the last column ; clamp those of arrays built by hand
+                            let last  = last.min(column_extents.len());
+                            let first = first.clamp(1, last.max(1));
+                            match (column_extents.get(first - 1), last.checked_sub(1).and_then(|i| column_extents.get(i))) {
+                                (Some(&(x_start, _)), Some(&(_, x_end))) => (x_start, x_end, dashed),
+                                _ => continue,
+                            }
+                        },
+                    };
+
+                    if line_top != y {
+                        lines.add_node(kern!(vert: line_top - y));
+                        y = line_top;
+                    }
+                    lines.add_node(rule_measurements.horizontal_line(x_start, x_end - x_start, dashed));
+                    y += rule_measurements.rule_width;
+
+                    if let RowSeparator::HorizontalLine { .. } = separator {
+                        line_top += rule_measurements.rule_width;
+                    }
+                }
+            }
+            if total_height > y {
+                lines.add_node(kern!(vert: total_height - y));
+            }
+
+            let body = hbox.build();
+            let lines = lines.build();
+            let lines_width = lines.width;
+
+            hbox = builders::HBox::new();
+            hbox.add_node(lines);
+            hbox.add_node(kern!(horz: - lines_width));
+            hbox.add_node(body);
+        }
+
 
         // TODO: Reference array vertical alignment (optional [bt] arguments)
         // Vertically center the array on axis.
@@ -1094,6 +1561,71 @@ fn draw_vertical_bars<F>(hbox: &mut builders::HBox<F>, n_bars: u8, rule_measurem
 
 
 
+impl RuleMeasurements {
+    /// Total vertical space taken by the horizontal lines between two rows.
+    fn row_separators_height(&self, separators: &[RowSeparator]) -> Unit<Px> {
+        let n_full_lines = separators.iter()
+            .filter(|separator| matches!(separator, RowSeparator::HorizontalLine { .. }))
+            .count();
+        if n_full_lines == 0 {
+            return Unit::ZERO;
+        }
+        self.rule_width.scale(n_full_lines as f64) + self.double_rule_sep.scale((n_full_lines - 1) as f64)
+    }
+
+    /// A horizontal line of width `width` starting at `x_start`, either plain or dashed.
+    fn horizontal_line<'f, F>(&self, x_start: Unit<Px>, width: Unit<Px>, dashed: bool) -> LayoutNode<'f, F> {
+        let mut hbox = builders::HBox::new();
+        hbox.add_node(kern!(horz: x_start));
+        if dashed {
+            let dash = DASH_LINE_DASH * Unit::standard_pt_to_px();
+            let gap  = DASH_LINE_GAP  * Unit::standard_pt_to_px();
+            let mut x = Unit::ZERO;
+            while x < width {
+                let dash_width = Unit::min(dash, width - x);
+                hbox.add_node(rule!(width: dash_width, height: self.rule_width));
+                x += dash_width;
+                if x + gap < width {
+                    hbox.add_node(kern!(horz: gap));
+                }
+                x += gap;
+            }
+        }
+        else {
+            hbox.add_node(rule!(width: width, height: self.rule_width));
+        }
+        hbox.build()
+    }
+}
+
+/// Stacks a superscript and a subscript, shifted from the baseline by `adjust_up` and `adjust_down` respectively.
+fn script_column<'f, F>(sup: Option<Layout<'f, F>>, sub: Option<Layout<'f, F>>, adjust_up: Unit<Px>, adjust_down: Unit<Px>) -> LayoutNode<'f, F> {
+    let mut contents = builders::VBox::new();
+    let adjust_down = if sub.is_some() { adjust_down } else { Unit::ZERO };
+    if let Some(sup) = sup {
+        let sub_height = sub.as_ref().map_or(Unit::ZERO, |sub| sub.height);
+        let corrected_adjust = adjust_up - sub_height + adjust_down;
+        contents.add_node(sup.as_node());
+        contents.add_node(kern!(vert: corrected_adjust));
+    }
+
+    contents.set_offset(adjust_down);
+    if let Some(sub) = sub {
+        contents.add_node(sub.as_node());
+    }
+    contents.build()
+}
+
+/// A straight line segment of zero width going from `start` to `end` (both relative to the left end of its baseline).
+fn line_segment<'f, F>(start: (Unit<Px>, Unit<Px>), end: (Unit<Px>, Unit<Px>), thickness: Unit<Px>) -> LayoutNode<'f, F> {
+    LayoutNode {
+        width:  Unit::ZERO,
+        height: max!(Unit::ZERO, start.1, end.1),
+        depth:  Unit::min(Unit::ZERO, Unit::min(start.1, end.1)),
+        node:   LayoutVariant::Line(LineSegment { start, end, thickness }),
+    }
+}
+
 fn extend_delimiter<'a, 'f, F : MathFont>(
     symbol : Symbol, 
     height_content: Unit<Px>,
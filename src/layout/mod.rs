@@ -199,6 +199,10 @@ pub enum LayoutVariant<'f, F> {
     Color(ColorChange<'f, F>),
     /// A filled rectangle
     Rule,
+    /// A rectangle filled with the given color rather than the current color, e.g. the background of `\colorbox{..}{..}`
+    FilledRect(RGBA),
+    /// A straight line segment, e.g. the strokes of `\cancel{..}`
+    Line(LineSegment),
     /// Some (possibly negative) spacing
     Kern,
 }
@@ -212,6 +216,8 @@ impl<'f, F> Clone for LayoutVariant<'f, F> {
             LayoutVariant::Glyph(glyph)           => LayoutVariant::Glyph(glyph.clone()),
             LayoutVariant::Color(color_change)    => LayoutVariant::Color(color_change.clone()),
             LayoutVariant::Rule                   => LayoutVariant::Rule,
+            LayoutVariant::FilledRect(color)      => LayoutVariant::FilledRect(*color),
+            LayoutVariant::Line(line)             => LayoutVariant::Line(*line),
             LayoutVariant::Kern                   => LayoutVariant::Kern,
         }
     }
@@ -234,6 +240,19 @@ impl<'f, F> Clone for ColorChange<'f, F> {
     }
 }
 
+/// A straight line segment drawn in the current color.
+/// Its end points are given relative to the left end of the baseline of the node, with the y-axis pointing up.
+/// They may lie outside of the box of the node (e.g. when the node has zero width).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineSegment {
+    /// Point where the segment starts
+    pub start: (Unit<Px>, Unit<Px>),
+    /// Point where the segment ends
+    pub end: (Unit<Px>, Unit<Px>),
+    /// Thickness of the segment
+    pub thickness: Unit<Px>,
+}
+
 /// Place nodes in a grid-like pattern. 
 /// The number of rows and columns is determined automatically
 pub struct Grid<'f, F> {
@@ -428,6 +447,8 @@ impl<'f, F> fmt::Debug for LayoutNode<'f, F> {
                 LayoutVariant::VerticalBox(ref vb)   => write!(f, "VBox({:#?})", vb.contents),
                 LayoutVariant::Glyph(ref gly)        => write!(f, "Glyph({:#?})", gly),
                 LayoutVariant::Rule                  => write!(f, "Rule()"),
+                LayoutVariant::FilledRect(ref color) => write!(f, "FilledRect({:#?})", color),
+                LayoutVariant::Line(ref line)        => write!(f, "Line({:#?})", line),
                 LayoutVariant::Kern                  => {
                     let kern = if self.width.is_zero() {
                         self.height
@@ -447,6 +468,8 @@ impl<'f, F> fmt::Debug for LayoutNode<'f, F> {
                 LayoutVariant::VerticalBox(ref vb)   => write!(f, "VBox({:?})", vb.contents),
                 LayoutVariant::Glyph(ref gly)        => write!(f, "Glyph({:?})", gly),
                 LayoutVariant::Rule                  => write!(f, "Rule()"),
+                LayoutVariant::FilledRect(ref color) => write!(f, "FilledRect({:?})", color),
+                LayoutVariant::Line(ref line)        => write!(f, "Line({:?})", line),
                 LayoutVariant::Kern                  => {
                     let kern = if self.width.is_zero() {
                         self.height
@@ -728,11 +751,25 @@ impl<'a, 'f, F> LayoutSettings<'a, 'f, F> {
             ..self
         }
     }
+
+    fn with_script(self) -> Self {
+        LayoutSettings {
+            style: Style::Script,
+            ..self
+        }
+    }
+
+    fn with_scriptscript(self) -> Self {
+        LayoutSettings {
+            style: Style::ScriptScript,
+            ..self
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{dimensions::{Unit, units::{FUnit, Ratio, FontSize, Px, Em}}, parser::parse, font::{backend::ttf_parser::TtfMathFont, FontContext}, layout::{LayoutSettings, engine::layout}};
+    use crate::{dimensions::{Unit, units::{FUnit, Ratio, FontSize, Px, Em}}, parser::parse, font::{backend::ttf_parser::TtfMathFont, Family, FontContext, Style}, layout::{LayoutSettings, engine::layout}};
 
 
     #[test]
@@ -775,4 +812,102 @@ mod tests {
         );
 
     }
+
+    #[test]
+    fn inline_continued_fraction() {
+        const XITS_FONT_BYTES : &[u8] = include_bytes!("../../resources/XITS_Math.otf");
+        let font = TtfMathFont::new(ttf_parser::Face::parse(XITS_FONT_BYTES, 0).unwrap()).unwrap();
+        let ctx = FontContext::new(&font);
+        let config = LayoutSettings::new(&ctx).layout_style(super::Style::Text);
+
+        // Only the parts of `\cfrac` are in display style, the fraction itself uses the shifts and gaps of the current style
+        let cfrac = layout(&parse(r"\cfrac{1}{2}").unwrap(), config).unwrap().size();
+        let frac  = layout(&parse(r"\frac{\displaystyle 1}{\displaystyle 2}").unwrap(), config).unwrap().size();
+        assert_eq!((cfrac.height, cfrac.depth), (frac.height, frac.depth));
+    }
+
+    #[test]
+    fn text_font_glyphs() {
+        const XITS_FONT_BYTES     : &[u8] = include_bytes!("../../resources/XITS_Math.otf");
+        const GARAMOND_FONT_BYTES : &[u8] = include_bytes!("../../resources/Garamond_Math.otf");
+
+        let math_font = TtfMathFont::new(ttf_parser::Face::parse(XITS_FONT_BYTES, 0).unwrap()).unwrap();
+        let text_face = ttf_parser::Face::parse(GARAMOND_FONT_BYTES, 0).unwrap();
+        let text_gid = text_face.glyph_index('1').unwrap();
+        let text_highest_point_one = Unit::<FUnit>::new(text_face.glyph_bounding_box(text_gid).unwrap().y_max.into());
+        let text_funit_per_em = Unit::<Ratio<FUnit, Em>>::new(text_face.units_per_em().into());
+        let text_font = TtfMathFont::new_text_font(text_face);
+        let ctx = FontContext::new(&math_font).with_text_font(&text_font);
+
+        // Text is set in the text font, math in the math font
+        let font_size = Unit::<FontSize>::new(10.);
+        let config = LayoutSettings::new(&ctx).font_size(font_size.unitless(FontSize::new()));
+        let text_layout = layout(&parse(r"\text{1}").unwrap(), config).unwrap();
+        let glyph = super::is_symbol(&text_layout.contents).unwrap();
+        assert!(std::ptr::eq(glyph.font, text_font.font()));
+        assert_eq!(glyph.gid, text_gid.into());
+        assert_close!(
+            Unit::<Px>::new(text_layout.size().height),
+            text_highest_point_one * text_funit_per_em.recip() * font_size * Unit::standard_pt_to_px(),
+            Unit::<Px>::new(1e-5)
+        );
+
+        let math_layout = layout(&parse("1").unwrap(), config).unwrap();
+        let glyph = super::is_symbol(&math_layout.contents).unwrap();
+        assert!(std::ptr::eq(glyph.font, &math_font));
+    }
+
+    #[test]
+    fn text_shaping() {
+        const XITS_FONT_BYTES : &[u8] = include_bytes!("../../resources/XITS_Math.otf");
+        const FIRA_FONT_BYTES : &[u8] = include_bytes!("../../resources/FiraMath_Regular.otf");
+
+        let xits_face = ttf_parser::Face::parse(XITS_FONT_BYTES, 0).unwrap();
+        let glyph_advance = |c : char| Unit::<FUnit>::new(xits_face.glyph_hor_advance(xits_face.glyph_index(c).unwrap()).unwrap().into());
+        let (advance_t, advance_a, advance_e, advance_space) = (glyph_advance('T'), glyph_advance('a'), glyph_advance('e'), glyph_advance(' '));
+        let xits = TtfMathFont::new(xits_face).unwrap();
+        let ctx = FontContext::new(&xits);
+        let text_style = Style::new().with_family(Family::Roman);
+
+        // Kerning, from the GPOS table
+        let shaped = ctx.shape_text("Ta", text_style).unwrap();
+        assert_eq!(shaped.len(), 2);
+        assert!(shaped[0].advance < advance_t);
+        assert_eq!(shaped[1].advance, advance_a);
+
+        // Spaces have the advance of the space glyph
+        let shaped = ctx.shape_text("a a", text_style).unwrap();
+        assert!(shaped[1].glyph.is_none());
+        assert_eq!(shaped[1].advance, advance_space);
+
+        // A combining mark is centered over its base and does not advance
+        let shaped = ctx.shape_text("e\u{301}", text_style).unwrap();
+        assert_eq!(shaped.len(), 2);
+        assert_eq!(shaped[0].advance, advance_e);
+        assert_eq!(shaped[1].advance, Unit::ZERO);
+        let mark = shaped[1].glyph.as_ref().unwrap();
+        assert_close!(
+            advance_e + shaped[1].x_offset + (mark.bbox.0 + mark.bbox.2).scale(0.5),
+            advance_e.scale(0.5),
+            Unit::<FUnit>::new(1e-5)
+        );
+
+        // Ligatures, from the GSUB table
+        let fira = TtfMathFont::new(ttf_parser::Face::parse(FIRA_FONT_BYTES, 0).unwrap()).unwrap();
+        let ctx = FontContext::new(&fira);
+        let shaped = ctx.shape_text("office", text_style).unwrap();
+        assert_eq!(shaped.len(), 4);
+        let config = LayoutSettings::new(&ctx);
+        let text_layout = layout(&parse(r"\text{office}").unwrap(), config).unwrap();
+        assert_eq!(text_layout.contents.len(), 4);
+
+        // A font command keeps the text in the text font, so that its ligatures still apply
+        let fira_text = TtfMathFont::new_text_font(ttf_parser::Face::parse(FIRA_FONT_BYTES, 0).unwrap());
+        let ctx = FontContext::new(&xits).with_text_font(&fira_text);
+        let config = LayoutSettings::new(&ctx);
+        let text_layout = layout(&parse(r"\textbf{office}").unwrap(), config).unwrap();
+        assert_eq!(text_layout.contents.len(), 4);
+        let glyph = super::is_symbol(&text_layout.contents[..1]).unwrap();
+        assert!(std::ptr::eq(glyph.font, fira_text.font()));
+    }
 }
\ No newline at end of file
@@ -18,6 +18,8 @@ let math_font = rex::font::backend::ttf_parser::TtfMathFont::new(font).expect("T
 let font_context = rex::font::FontContext::new(&math_font);
 ```
 
+Text (e.g. `\text{..}`) is set with the math font, unless a text font, which needs no MATH table, is given with `font_context.with_text_font(&text_font)`, `text_font` being a `TextFont` created with `TtfMathFont::new_text_font`, which cannot be used as the math font. Text is shaped with the ligatures and kerning of the font, and combining marks are placed on the preceding character.
+
 Second, create the graphical backend, e.g. with `cairo` here
 
 ```no_run
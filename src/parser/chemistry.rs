@@ -0,0 +1,265 @@
+//! Parses the chemical formulas and equations of the `\ce{..}` command, which follows (a subset of) the syntax of the mhchem LaTeX package.
+//!
+//! The syntax supported is the following:
+//!   - letters are set upright, e.g. `H2O` or `(aq)`
+//!   - a number at the start of a term is a stoichiometric coefficient (e.g. `2H2`), a number after a formula is a subscript (e.g. `Ca(OH)2`)
+//!   - a sign right after a formula is a charge (e.g. `Na+` or `Cl-`) ; charges can also be given as superscripts (e.g. `SO4^2-` or `Fe^{3+}`)
+//!   - explicit sub- and superscripts preceding an element are placed on its left (e.g. `^{14}_{6}C`)
+//!   - `->`, `<-`, `<->` and `<=>` are reaction arrows, optionally followed by labels placed above and below them (e.g. `->[H2O][heat]`)
+//!   - ` + ` separates species, `*` joins adducts (e.g. `CuSO4*5H2O`), ` ^ ` and ` v ` mark a gas and a precipitate
+
+use unicode_math::TexSymbolType;
+
+use super::error::{ParseError, ParseResult};
+use super::nodes::{ExtensibleArrow, Scripts};
+use super::symbols::Symbol;
+use super::textoken::TexToken;
+use super::{codepoint_atom_type, script_nodes, upright, ParseNode};
+
+const ARROWS : [(&str, char); 4] = [
+    ("<=>", '⇌'),
+    ("<->", '↔'),
+    ("->",  '→'),
+    ("<-",  '←'),
+];
+
+/// Parses the tokens in the argument of `\ce{..}`.
+pub fn parse_chemistry<'a, I : Iterator<Item = TexToken<'a>>>(tokens : I) -> ParseResult<Vec<ParseNode>> {
+    let mut input = Vec::new();
+    for token in tokens {
+        input.push(match token {
+            TexToken::Char(c)     => c,
+            TexToken::WhiteSpace  => ' ',
+            TexToken::Superscript => '^',
+            TexToken::Subscript   => '_',
+            TexToken::BeginGroup  => '{',
+            TexToken::EndGroup    => '}',
+              TexToken::ControlSequence(_)
+            | TexToken::Alignment
+            | TexToken::Tilde
+            | TexToken::Argument(_)
+            | TexToken::Prime(_)
+            => return Err(ParseError::ExpectedChars),
+        });
+    }
+    ChemistryParser::new(input).parse()
+}
+
+struct ChemistryParser {
+    input : Vec<char>,
+    position : usize,
+    nodes : Vec<ParseNode>,
+    /// Whether the last item belongs to a formula, in which case a number following it is a subscript and a sign is a charge
+    after_formula : bool,
+    /// Whether the last character is a space (or the start of the input)
+    after_space : bool,
+    /// Scripts given before an element, to be placed on its left
+    presuperscript : Option<Vec<ParseNode>>,
+    presubscript : Option<Vec<ParseNode>>,
+}
+
+impl ChemistryParser {
+    fn new(input : Vec<char>) -> Self {
+        Self {
+            input,
+            position : 0,
+            nodes : Vec::new(),
+            after_formula : false,
+            after_space : true,
+            presuperscript : None,
+            presubscript : None,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input.get(self.position).copied()
+    }
+
+    fn starts_with(&self, pattern : &str) -> bool {
+        let mut remaining = self.input[self.position ..].iter();
+        pattern.chars().all(|c| remaining.next() == Some(&c))
+    }
+
+    /// Whether the character following the current one is a space or the end of the input
+    fn is_isolated(&self) -> bool {
+        matches!(self.input.get(self.position + 1), None | Some(' '))
+    }
+
+    /// Consumes and returns the characters satisfying the predicate
+    fn take_while(&mut self, predicate : impl Fn(char) -> bool) -> String {
+        let mut taken = String::new();
+        while let Some(c) = self.peek().filter(|c| predicate(*c)) {
+            taken.push(c);
+            self.position += 1;
+        }
+        taken
+    }
+
+    /// Consumes the content enclosed between `open` and `close`, the opening character being the current one
+    fn take_enclosed(&mut self, open : char, close : char) -> ParseResult<Vec<char>> {
+        let mut depth = 0;
+        let start = self.position + 1;
+        while let Some(c) = self.peek() {
+            self.position += 1;
+            if c == open {
+                depth += 1;
+            }
+            else if c == close {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(self.input[start .. self.position - 1].to_vec());
+                }
+            }
+        }
+        Err(ParseError::UnmatchedBrackets)
+    }
+
+    fn parse(mut self) -> ParseResult<Vec<ParseNode>> {
+        while let Some(c) = self.peek() {
+            if let Some(&(pattern, arrow)) = ARROWS.iter().find(|(pattern, _)| self.starts_with(pattern)) {
+                self.position += pattern.chars().count();
+                self.arrow(arrow)?;
+                self.after_formula = false;
+                self.after_space = false;
+                continue;
+            }
+
+            match c {
+                ' ' => {
+                    self.position += 1;
+                    self.after_formula = false;
+                    self.after_space = true;
+                    continue;
+                },
+                '^' | 'v' if self.after_space && self.is_isolated() => {
+                    self.position += 1;
+                    let codepoint = if c == '^' { '↑' } else { '↓' };
+                    self.nodes.push(ParseNode::Symbol(Symbol { codepoint, atom_type: TexSymbolType::Ordinary }));
+                },
+                '^' | '_' => {
+                    self.position += 1;
+                    let script = self.script_argument()?;
+                    self.script(script, c == '^')?;
+                },
+                '0' ..= '9' if self.after_formula => {
+                    let count = self.take_while(|c| c.is_ascii_digit());
+                    self.script(script_nodes(&count)?, false)?;
+                },
+                '0' ..= '9' => {
+                    let coefficient = self.take_while(|c| c.is_ascii_digit() || c == '.');
+                    self.nodes.extend(coefficient.chars().map(upright));
+                },
+                '+' | '-' if self.after_formula => {
+                    let charge = self.take_while(|c| c == '+' || c == '-');
+                    self.script(script_nodes(&charge)?, true)?;
+                },
+                '+' | '-' | '*' | '=' => {
+                    self.position += 1;
+                    let symbol = match c {
+                        '+' => Symbol { codepoint: '+', atom_type: TexSymbolType::Binary },
+                        '-' => Symbol { codepoint: '−', atom_type: TexSymbolType::Binary },
+                        '*' => Symbol { codepoint: '⋅', atom_type: TexSymbolType::Binary },
+                        _   => Symbol { codepoint: '=', atom_type: TexSymbolType::Relation },
+                    };
+                    self.nodes.push(ParseNode::Symbol(symbol));
+                    self.after_formula = false;
+                },
+                c if c.is_alphabetic() => {
+                    // An element is an uppercase letter followed by lowercase letters
+                    self.position += 1;
+                    let mut letters = vec![upright(c)];
+                    letters.extend(self.take_while(|c| c.is_lowercase()).chars().map(upright));
+
+                    if self.presuperscript.is_some() || self.presubscript.is_some() {
+                        letters[0] = ParseNode::Scripts(Scripts {
+                            base: Some(Box::new(letters[0].clone())),
+                            superscript: None,
+                            subscript: None,
+                            presuperscript: self.presuperscript.take(),
+                            presubscript: self.presubscript.take(),
+                        });
+                    }
+                    self.nodes.extend(letters);
+                    self.after_formula = true;
+                },
+                '{' | '}' => {
+                    self.position += 1;
+                },
+                c => {
+                    self.position += 1;
+                    let atom_type = codepoint_atom_type(c).ok_or(ParseError::UnrecognizedSymbol(c))?;
+                    self.nodes.push(ParseNode::Symbol(Symbol { codepoint: c, atom_type }));
+                    self.after_formula = matches!(atom_type, TexSymbolType::Close);
+                },
+            }
+            self.after_space = false;
+        }
+
+        if self.presuperscript.is_some() || self.presubscript.is_some() {
+            self.nodes.push(ParseNode::Scripts(Scripts {
+                base: None,
+                superscript: self.presuperscript,
+                subscript: self.presubscript,
+                presuperscript: None,
+                presubscript: None,
+            }));
+        }
+        Ok(self.nodes)
+    }
+
+    /// Parses the labels following an arrow, e.g. the `[H2O][heat]` in `->[H2O][heat]`
+    fn arrow(&mut self, arrow : char) -> ParseResult<()> {
+        let mut labels = Vec::with_capacity(2);
+        while labels.len() < 2 && self.peek() == Some('[') {
+            let label = self.take_enclosed('[', ']')?;
+            labels.push(ChemistryParser::new(label).parse()?);
+        }
+        let mut labels = labels.into_iter();
+        let over  = labels.next().unwrap_or_default();
+        let under = labels.next();
+        self.nodes.push(ParseNode::ExtensibleArrow(ExtensibleArrow { arrow, over, under }));
+        Ok(())
+    }
+
+    /// Parses the argument of `^` or `_`, either enclosed in braces or a sequence of letters, digits and signs
+    fn script_argument(&mut self) -> ParseResult<Vec<ParseNode>> {
+        let script : String = match self.peek() {
+            Some('{') => self.take_enclosed('{', '}')?.into_iter().collect(),
+            _ => self.take_while(|c| c.is_alphanumeric() || c == '+' || c == '-'),
+        };
+        if script.is_empty() {
+            return Err(ParseError::MissingSubSuperScript);
+        }
+        script_nodes(&script)
+    }
+
+    /// Attaches a script to the last formula ; at the start of a term, it is kept to be placed on the left of the next element.
+    fn script(&mut self, script : Vec<ParseNode>, is_superscript : bool) -> ParseResult<()> {
+        if !self.after_formula {
+            let prescript = if is_superscript { &mut self.presuperscript } else { &mut self.presubscript };
+            if prescript.is_some() {
+                return Err(ParseError::TooManySubscriptsOrSuperscripts);
+            }
+            *prescript = Some(script);
+            return Ok(());
+        }
+
+        let mut scripts = match self.nodes.pop() {
+            Some(ParseNode::Scripts(scripts)) => scripts,
+            node => Scripts {
+                base: node.map(Box::new),
+                superscript: None,
+                subscript: None,
+                presuperscript: None,
+                presubscript: None,
+            },
+        };
+        let slot = scripts.get_script(is_superscript);
+        if slot.is_some() {
+            return Err(ParseError::TooManySubscriptsOrSuperscripts);
+        }
+        *slot = Some(script);
+        self.nodes.push(ParseNode::Scripts(scripts));
+        Ok(())
+    }
+}
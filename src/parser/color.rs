@@ -4,6 +4,11 @@
 
 use std::str::FromStr;
 
+use super::error::{ParseError, ParseResult};
+use super::macros::CommandCollection;
+use super::textoken::TexToken;
+use super::Parser;
+
 
 
 /// A color with alpha values
@@ -13,9 +18,19 @@ pub struct RGBA(pub u8, pub u8, pub u8, pub u8);
 
 
 /// Type of errors encountered while parsing the color's name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ColorParseError {
     /// Color does not start with # but its name is not the ASCII name of a CSS color
     UnknownColorName,
+    /// Color starts with # but is not of the form #RGB, #RGBA, #RRGGBB or #RRGGBBAA
+    InvalidHexColor,
+    /// The components of `rgb(..)`, `rgba(..)` or of a color model (e.g. the `0.1,0.2,0.3` of `\color[rgb]{0.1,0.2,0.3}`) 
+    /// are not numbers in the expected range, or are not in the expected number
+    InvalidComponents,
+    /// The color model (e.g. the `rgb` of `\color[rgb]{..}`) is not one of `rgb`, `RGB`, `HTML`, `gray`, `cmyk` and `cmy`
+    UnknownColorModel,
+    /// A percentage of a mix expression (e.g. the `30` of `red!30!blue`) is not a number between 0 and 100
+    InvalidMixPercentage,
 }
 
 
@@ -26,12 +41,82 @@ impl FromStr for RGBA {
 
     /// Match a valid color token. Valid color tokens are:
     ///  - Ascii name for css color (ie: `red`).
-    ///  - #RRGGBB (ie: `#ff0000` for red)
-    ///  - #RRGGBBAA (ie: `#00000000` for transparent)
+    ///  - #RRGGBB (ie: `#ff0000` for red), or the shorter #RGB (ie: `#f00`)
+    ///  - #RRGGBBAA (ie: `#00000000` for transparent), or the shorter #RGBA (ie: `#0000`)
+    ///  - `rgb(r, g, b)` and `rgba(r, g, b, a)` like in CSS (ie: `rgba(255, 0, 0, 0.5)` for translucent red)
     ///  - `transparent`
-    // TODO: implement missing tokens
+    ///  - mix expressions of the above like in the xcolor LaTeX package (ie: `red!30!blue` for 30% red and 70% blue, `red!30` for 30% red and 70% white), 
+    ///    optionally preceded by `-` for the complementary color (ie: `-red` for cyan)
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Self::from_name(s).ok_or(ColorParseError::UnknownColorName)
+        ColorPalette::new().evaluate(s)
+    }
+}
+
+
+/// A collection of named colors, as defined by `\definecolor{name}{model}{specification}`.  
+/// The names of a palette take precedence over the names of CSS colors.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ColorPalette(Vec<(String, RGBA)>);
+
+impl ColorPalette {
+    /// Creates a new empty [`ColorPalette`]
+    pub const fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Retrieves a color by name
+    pub fn get(&self, name: &str) -> Option<RGBA> {
+        self.0
+            .iter()
+            .find(|(color_name, _)| color_name == name)
+            .map(|(_, color)| *color)
+    }
+
+    /// Registers a color under the given name, replacing any previous color with the same name
+    pub fn define(&mut self, name: &str, color: RGBA) {
+        self.0.retain(|(color_name, _)| color_name != name);
+        self.0.push((name.to_string(), color));
+    }
+
+    /// Parses the content of a TeX file composed of a series of `\definecolor{..}{..}{..}` statements into a palette
+    pub fn parse(input: &str) -> ParseResult<Self> {
+        let command_collection = CommandCollection::new();
+        let mut parser = Parser::new(&command_collection, input);
+        // Statements are checked before expansion, as a `\def` or a `\newcommand` would otherwise vanish without a trace
+        while let Some(token) = parser.token_iter.peek_unexpanded_non_whitespace_token() {
+            if token != TexToken::ControlSequence("definecolor") {
+                return Err(ParseError::ExpectedColorDefinition);
+            }
+            parser.token_iter.next_token()?;
+            parser.parse_color_definition("definecolor")?;
+        }
+        Ok(parser.color_palette)
+    }
+
+    /// Evaluates a color expression, with the syntax described in [`RGBA::from_str`](std::str::FromStr::from_str), 
+    /// where the names of this palette can be used alongside the names of CSS colors.
+    pub fn evaluate(&self, expression: &str) -> Result<RGBA, ColorParseError> {
+        let (complement, expression) = match expression.strip_prefix('-') {
+            Some(expression) => (true, expression),
+            None             => (false, expression),
+        };
+
+        // In `red!30!blue!50!green`, `red` is mixed with `blue` and then the result is mixed with `green`
+        let mut parts = expression.split('!');
+        let mut color = RGBA::from_single_color(parts.next().unwrap_or_default(), self)?;
+        while let Some(percentage) = parts.next() {
+            let percentage = percentage.trim().parse::<f64>()
+                .ok()
+                .filter(|percentage| (0. ..= 100.).contains(percentage))
+                .ok_or(ColorParseError::InvalidMixPercentage)?;
+            let other = match parts.next() {
+                Some(other) => RGBA::from_single_color(other, self)?,
+                None        => RGBA(0xff, 0xff, 0xff, 0xff),
+            };
+            color = color.mix(other, percentage / 100.);
+        }
+
+        Ok(if complement { color.complement() } else { color })
     }
 }
 
@@ -43,6 +128,117 @@ impl RGBA {
             _ => None
         }
     }
+
+    /// Given a color model of the xcolor LaTeX package and a specification in this model, returns the corresponding [`RGBA`] value.  
+    /// The supported models are:
+    ///  - `rgb`: three numbers between 0 and 1 (ie: `1,0.5,0` for orange)
+    ///  - `RGB`: three integers between 0 and 255 (ie: `255,128,0`)
+    ///  - `HTML`: six hexadecimal digits (ie: `FF8000`)
+    ///  - `gray`: a number between 0 (black) and 1 (white)
+    ///  - `cmyk` and `cmy`: four (resp. three) numbers between 0 and 1
+    pub fn from_model(model: &str, specification: &str) -> Result<RGBA, ColorParseError> {
+        let specification = specification.trim();
+        match model.trim() {
+            "HTML" => {
+                if specification.len() != 6 {
+                    return Err(ColorParseError::InvalidComponents);
+                }
+                Self::from_hex(specification).ok_or(ColorParseError::InvalidComponents)
+            },
+            "RGB" => {
+                let [r, g, b] = components(specification, 255.)?;
+                Ok(RGBA(r.round() as u8, g.round() as u8, b.round() as u8, 0xff))
+            },
+            "rgb" => {
+                let [r, g, b] = components(specification, 1.)?;
+                Ok(RGBA(to_byte(r), to_byte(g), to_byte(b), 0xff))
+            },
+            "gray" => {
+                let [gray] = components(specification, 1.)?;
+                Ok(RGBA(to_byte(gray), to_byte(gray), to_byte(gray), 0xff))
+            },
+            "cmy" => {
+                let [c, m, y] = components(specification, 1.)?;
+                Ok(RGBA(to_byte(1. - c), to_byte(1. - m), to_byte(1. - y), 0xff))
+            },
+            "cmyk" => {
+                // Conversion used by xcolor
+                let [c, m, y, k] = components(specification, 1.)?;
+                let channel = |x : f64| to_byte(1. - (x + k).min(1.));
+                Ok(RGBA(channel(c), channel(m), channel(y), 0xff))
+            },
+            _ => Err(ColorParseError::UnknownColorModel),
+        }
+    }
+
+    /// Parses a color name (looked up in the palette first), a hexadecimal color (ie: `#ff0000`) or a CSS function (ie: `rgb(255, 0, 0)`)
+    fn from_single_color(s: &str, palette: &ColorPalette) -> Result<RGBA, ColorParseError> {
+        let s = s.trim();
+        if let Some(hex) = s.strip_prefix('#') {
+            return Self::from_hex(hex).ok_or(ColorParseError::InvalidHexColor);
+        }
+        if let Some(arguments) = s.strip_prefix("rgba(").and_then(|s| s.strip_suffix(')')) {
+            let (rgb, alpha) = arguments.rsplit_once(',').ok_or(ColorParseError::InvalidComponents)?;
+            let [r, g, b] = components(rgb, 255.)?;
+            let [alpha] = components(alpha, 1.)?;
+            return Ok(RGBA(r.round() as u8, g.round() as u8, b.round() as u8, to_byte(alpha)));
+        }
+        if let Some(arguments) = s.strip_prefix("rgb(").and_then(|s| s.strip_suffix(')')) {
+            let [r, g, b] = components(arguments, 255.)?;
+            return Ok(RGBA(r.round() as u8, g.round() as u8, b.round() as u8, 0xff));
+        }
+        palette.get(s)
+            .or_else(|| Self::from_name(s))
+            .ok_or(ColorParseError::UnknownColorName)
+    }
+
+    /// Parses the hexadecimal digits of `#RGB`, `#RGBA`, `#RRGGBB` or `#RRGGBBAA`
+    fn from_hex(hex: &str) -> Option<RGBA> {
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let digit = |i : usize| u8::from_str_radix(&hex[i .. i + 1], 16).ok();
+        let byte  = |i : usize| u8::from_str_radix(&hex[2 * i .. 2 * i + 2], 16).ok();
+        match hex.len() {
+            3 => Some(RGBA(digit(0)? * 0x11, digit(1)? * 0x11, digit(2)? * 0x11, 0xff)),
+            4 => Some(RGBA(digit(0)? * 0x11, digit(1)? * 0x11, digit(2)? * 0x11, digit(3)? * 0x11)),
+            6 => Some(RGBA(byte(0)?, byte(1)?, byte(2)?, 0xff)),
+            8 => Some(RGBA(byte(0)?, byte(1)?, byte(2)?, byte(3)?)),
+            _ => None,
+        }
+    }
+
+    /// Mixes `ratio` of this color with `1 - ratio` of the other one
+    fn mix(self, other: RGBA, ratio: f64) -> RGBA {
+        let channel = |x : u8, y : u8| (ratio * x as f64 + (1. - ratio) * y as f64).round() as u8;
+        RGBA(channel(self.0, other.0), channel(self.1, other.1), channel(self.2, other.2), channel(self.3, other.3))
+    }
+
+    /// The complementary color, i.e. `1 - c` on each channel (except alpha)
+    fn complement(self) -> RGBA {
+        RGBA(0xff - self.0, 0xff - self.1, 0xff - self.2, self.3)
+    }
+}
+
+/// Parses `N` comma-separated numbers between 0 and `max`
+fn components<const N: usize>(s: &str, max: f64) -> Result<[f64; N], ColorParseError> {
+    let mut components = [0.; N];
+    let mut values = s.split(',');
+    for component in components.iter_mut() {
+        *component = values.next()
+            .and_then(|value| value.trim().parse::<f64>().ok())
+            .filter(|value| (0. ..= max).contains(value))
+            .ok_or(ColorParseError::InvalidComponents)?;
+    }
+    if values.next().is_some() {
+        return Err(ColorParseError::InvalidComponents);
+    }
+    Ok(components)
+}
+
+/// Converts a number between 0 and 1 to a channel value between 0 and 255
+fn to_byte(x: f64) -> u8 {
+    (x * 255.).round() as u8
 }
 
 
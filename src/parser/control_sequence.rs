@@ -1,8 +1,8 @@
 use unicode_math::TexSymbolType;
 
-use crate::{dimensions::{units::Em, AnyUnit, Unit}, font::{Family, Weight}, layout::{constants, Style as LayoutStyle}, parser::{nodes::{BarThickness, MathStyle}, symbols::Symbol}, RGBA};
+use crate::{dimensions::{units::Em, AnyUnit, Unit}, font::{Family, Weight}, layout::{constants, Style as LayoutStyle}, parser::{nodes::{BarThickness, BoxWidth, CancelKind, MathStyle}, symbols::Symbol}, RGBA};
 
-use super::{error::{ParseError, ParseResult}, textoken::TexToken};
+use super::{color::{ColorPalette, ColorParseError}, error::{ParseError, ParseResult}, textoken::TexToken};
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum PrimitiveControlSequence {
@@ -10,31 +10,88 @@ pub enum PrimitiveControlSequence {
     /// The enclosed argument gives the Unicode corresponding to this character.
     Radical(char),
     Rule,
-    /// Represents ReX's command `\color{..}{..}`
+    /// Represents `\color{..}`, which colors the rest of the current group
     Color,
+    /// Represents `\textcolor{..}{..}`, which colors its second argument
+    TextColor,
+    /// Represents `\definecolor{..}{..}{..}`, which adds a named color to the palette of the parser
+    DefineColor,
     /// Represents ReX's command `\blue{..}`, `\red{..}`
     ColorLit(RGBA),
+    /// Represents `\boxed{..}`, `\fbox{..}`, `\colorbox{..}{..}` and `\fcolorbox{..}{..}{..}`.  
+    /// `text_mode` tells whether the framed argument is text (as in `\fbox`) rather than math (as in `\boxed`).
+    /// `border` tells whether a frame is drawn and `fill` whether the command takes a background color ; 
+    /// when both are set (`\fcolorbox`), the frame color comes first.
+    Framed { text_mode: bool, border: bool, fill: bool },
     /// Represents LaTeX `\frac{..}`
     Fraction(Option<Symbol>, Option<Symbol>, BarThickness, MathStyle),
+    /// Represents `\genfrac{..}{..}{..}{..}{..}{..}`, whose delimiters, bar thickness and style are given as arguments
+    GenFrac,
+    /// Represents `\cfrac[..]{..}{..}` ; the optional argument `l` or `r` aligns the numerator to the left or right
+    ContinuedFraction,
+    /// Represents the TeX infix commands `\over`, `\atop` and `\choose`, which split the current group into a numerator and a denominator
+    InfixFraction(Option<Symbol>, Option<Symbol>, BarThickness),
     /// Represents `\limits` and `\nolimits` control sequences (cf [here](https://texfaq.org/FAQ-limits))
     Limits(bool),
     ExtendedDelimiter(DelimiterSize, TexSymbolType),
     Kerning(AnyUnit),
+    /// Represents `\kern`, `\hskip`, `\hspace{..}`, `\mkern` and `\mskip`, which are followed by an explicit dimension (e.g. `\kern3pt`).  
+    /// `math_units` tells whether the dimension must be given in `mu` (as for `\mkern` and `\mskip`) ; 
+    /// `star_form` tells whether the command accepts a `*` (as in `\hspace*{..}`), which makes no difference in ReX.
+    /// The stretch and shrink components of glue (`plus ..` and `minus ..`) are not supported.
+    Skip { math_units: bool, star_form: bool },
     StyleCommand(LayoutStyle),
     AtomChange(TexSymbolType),
     TextOperator(&'static str, bool),
+    /// Represents `\bmod`, a binary operator
+    BinaryMod,
+    /// Represents `\pmod{..}`, `\pod{..}` and `\mod{..}` ; `parenthesized` tells whether the argument is enclosed in parentheses 
+    /// and `word` whether it is preceded by "mod".
+    Mod { parenthesized: bool, word: bool },
+    /// Represents `\boldsymbol{..}` and `\bm{..}`, which embolden symbols (cf [`Style::with_bold_symbol`](crate::font::Style::with_bold_symbol))
+    BoldSymbol,
+    /// Represents `\pmb{..}`, which emboldens its argument by overstriking it
+    PoorMansBold,
+    /// Represents `\mathchoice{..}{..}{..}{..}`, whose arguments are used in display, text, script and scriptscript style respectively
+    MathChoice,
     SubStack(TexSymbolType),
+    /// Represents `\ce{..}`, whose argument is a chemical formula or equation (cf [`chemistry`](super::chemistry))
+    Chemistry,
+    /// Represents `\num{..}`, which formats a number (cf [`units`](super::units))
+    SiNumber,
+    /// Represents `\si{..}` and `\unit{..}`, which format a unit
+    SiUnit,
+    /// Represents `\SI{..}{..}` and `\qty{..}{..}`, which format a number followed by a unit
+    SiQuantity,
+    /// Represents `\sideset{..}{..}`, which places scripts on both sides of the operator following it (e.g. `\sideset{_a^b}{_c^d}\sum`)
+    SideSet,
+    /// Represents `\prescript{..}{..}{..}`, which places a superscript and a subscript on the left of its last argument
+    PreScript,
+    /// Represents `\overset{..}{..}`, `\underset{..}{..}` and `\stackrel{..}{..}` ; the first argument is placed as a label over (or under) the second one.  
+    /// Without an explicit atom type, the result has the atom type of the second argument.
+    OverUnderSet { under: bool, atom_type: Option<TexSymbolType> },
+    /// Represents `\xrightarrow[..]{..}`, `\xleftarrow[..]{..}`, etc. ; the enclosed character is the arrow to stretch.
+    ExtensibleArrow(char),
     SymbolCommand(Symbol),
     StyleChange { family: Option<Family>, weight: Option<Weight>, takes_arg : bool },
     /// Represents `\operatorname{..}` ; this puts the text in braces as mathrm and appropriately handles space around it.
     OperatorName,
     /// Underline `\underline{..}` ; creates a horizontal bar below box
     Underline,
+    /// Represents `\phantom{..}`, `\hphantom{..}`, `\vphantom{..}`, `\mathllap{..}`, `\mathrlap{..}` and `\mathclap{..}` ; cf [`nodes::ResizedBox`](super::nodes::ResizedBox)
+    ResizedBox { visible: bool, width: BoxWidth, keep_height: bool, keep_depth: bool },
+    /// Represents `\smash[..]{..}` ; the optional argument `t` (resp. `b`) only zeroes the height (resp. depth)
+    Smash,
+    /// Represents `\cancel{..}`, `\bcancel{..}` and `\xcancel{..}`
+    Cancel(CancelKind),
+    /// Represents `\cancelto{..}{..}` ; the first argument is the value the arrow points to
+    CancelTo,
     BeginEnv,
     EndEnv,
     Left,
     Middle,
     Right,
+    /// Represents `\text{..}` and the text font commands `\textbf{..}`, `\textit{..}`, `\textrm{..}`, `\textsf{..}` and `\texttt{..}`
     Text,
     /// The `\mbox` control sequence
     /// This command is like `\text`, except it ignores font size changes in sub-/super-scripts, etc.
@@ -101,9 +158,40 @@ impl PrimitiveControlSequence {
             "binom"  => Self::Fraction(OPEN_PAREN, CLOSE_PAREN, BarThickness::None,    MathStyle::NoChange),
             "tbinom" => Self::Fraction(OPEN_PAREN, CLOSE_PAREN, BarThickness::None,    MathStyle::Text),
             "dbinom" => Self::Fraction(OPEN_PAREN, CLOSE_PAREN, BarThickness::None,    MathStyle::Display),
+            "genfrac" => Self::GenFrac,
+            "cfrac"   => Self::ContinuedFraction,
+            "over"    => Self::InfixFraction(None,       None,        BarThickness::Default),
+            "atop"    => Self::InfixFraction(None,       None,        BarThickness::None),
+            "choose"  => Self::InfixFraction(OPEN_PAREN, CLOSE_PAREN, BarThickness::None),
 
             // Stacking commands
             "substack" => Self::SubStack(TexSymbolType::Inner),
+            "overset"  => Self::OverUnderSet { under: false, atom_type: None },
+            "underset" => Self::OverUnderSet { under: true,  atom_type: None },
+            "stackrel" => Self::OverUnderSet { under: false, atom_type: Some(TexSymbolType::Relation) },
+
+            // Chemistry
+            "ce" => Self::Chemistry,
+
+            // Physical units
+            "num"  => Self::SiNumber,
+            "si"   | "unit" => Self::SiUnit,
+            "SI"   | "qty"  => Self::SiQuantity,
+
+            // Pre-scripts
+            "sideset"   => Self::SideSet,
+            "prescript" => Self::PreScript,
+
+            // Extensible arrows
+            "xrightarrow"       => Self::ExtensibleArrow('\u{2192}'), // →
+            "xleftarrow"        => Self::ExtensibleArrow('\u{2190}'), // ←
+            "xleftrightarrow"   => Self::ExtensibleArrow('\u{2194}'), // ↔
+            "xRightarrow"       => Self::ExtensibleArrow('\u{21D2}'), // ⇒
+            "xLeftarrow"        => Self::ExtensibleArrow('\u{21D0}'), // ⇐
+            "xLeftrightarrow"   => Self::ExtensibleArrow('\u{21D4}'), // ⇔
+            "xmapsto"           => Self::ExtensibleArrow('\u{21A6}'), // ↦
+            "xhookrightarrow"   => Self::ExtensibleArrow('\u{21AA}'), // ↪
+            "xhookleftarrow"    => Self::ExtensibleArrow('\u{21A9}'), // ↩
 
 
             // Style-change command
@@ -151,10 +239,21 @@ impl PrimitiveControlSequence {
             " "     => Self::Kerning(SpaceKind::WordSpace.size()),
             "quad"  => Self::Kerning(SpaceKind::QuadSpace.size()),
             "qquad" => Self::Kerning(SpaceKind::DoubleQuadSpace.size()),
+            "kern"   => Self::Skip { math_units: false, star_form: false },
+            "hskip"  => Self::Skip { math_units: false, star_form: false },
+            "hspace" => Self::Skip { math_units: false, star_form: true  },
+            "mkern"  => Self::Skip { math_units: true,  star_form: false },
+            "mskip"  => Self::Skip { math_units: true,  star_form: false },
 
             "rule"       => Self::Rule,
             "underline"  => Self::Underline,
 
+            // Cancellations
+            "cancel"   => Self::Cancel(CancelKind::Forward),
+            "bcancel"  => Self::Cancel(CancelKind::Backward),
+            "xcancel"  => Self::Cancel(CancelKind::Cross),
+            "cancelto" => Self::CancelTo,
+
 
             // Display style changes
             "textstyle"         => Self::StyleCommand(LayoutStyle::Text),
@@ -163,6 +262,11 @@ impl PrimitiveControlSequence {
             "scriptscriptstyle" => Self::StyleCommand(LayoutStyle::ScriptScript),
 
             "text"              => Self::Text,
+            "textbf"            => Self::Text,
+            "textit"            => Self::Text,
+            "textrm"            => Self::Text,
+            "textsf"            => Self::Text,
+            "texttt"            => Self::Text,
             "mbox"              => Self::Mbox,
 
             // Atom-type changes
@@ -172,10 +276,39 @@ impl PrimitiveControlSequence {
 
             // Color related
             "color"   => Self::Color,
+            "textcolor"   => Self::TextColor,
+            "definecolor" => Self::DefineColor,
             "blue"    => Self::ColorLit(RGBA(0,0,0xff,0xff)),
             "red"     => Self::ColorLit(RGBA(0xff,0,0,0xff)),
             "gray"    => Self::ColorLit(RGBA(0x80,0x80,0x80,0xff)),
-            "phantom" => Self::ColorLit(RGBA(0,0,0,0)),
+
+            // Phantoms and overlaps
+            "phantom"  => Self::ResizedBox { visible: false, width: BoxWidth::Natural,       keep_height: true,  keep_depth: true  },
+            "hphantom" => Self::ResizedBox { visible: false, width: BoxWidth::Natural,       keep_height: false, keep_depth: false },
+            "vphantom" => Self::ResizedBox { visible: false, width: BoxWidth::OverlapRight,  keep_height: true,  keep_depth: true  },
+            "mathllap" => Self::ResizedBox { visible: true,  width: BoxWidth::OverlapLeft,   keep_height: true,  keep_depth: true  },
+            "mathrlap" => Self::ResizedBox { visible: true,  width: BoxWidth::OverlapRight,  keep_height: true,  keep_depth: true  },
+            "mathclap" => Self::ResizedBox { visible: true,  width: BoxWidth::OverlapCenter, keep_height: true,  keep_depth: true  },
+            "smash"    => Self::Smash,
+
+            // Frames
+            "boxed"     => Self::Framed { text_mode: false, border: true,  fill: false },
+            "fbox"      => Self::Framed { text_mode: true,  border: true,  fill: false },
+            "colorbox"  => Self::Framed { text_mode: true,  border: false, fill: true  },
+            "fcolorbox" => Self::Framed { text_mode: true,  border: true,  fill: true  },
+
+            // Modular arithmetic
+            "bmod" => Self::BinaryMod,
+            "pmod" => Self::Mod { parenthesized: true,  word: true  },
+            "pod"  => Self::Mod { parenthesized: true,  word: false },
+            "mod"  => Self::Mod { parenthesized: false, word: true  },
+
+            // Style-dependent content
+            "mathchoice" => Self::MathChoice,
+
+            // Bold symbols
+            "boldsymbol" | "bm" => Self::BoldSymbol,
+            "pmb"               => Self::PoorMansBold,
 
             // Operators with limits
             "det"     => Self::TextOperator("det", true),
@@ -246,9 +379,37 @@ impl PrimitiveControlSequence {
             "binom"  => 2,
             "tbinom" => 2,
             "dbinom" => 2,
+            "genfrac" => 6,
+            "cfrac"   => 2,
 
             // Stacking commands
             "substack" => 1,
+            "overset"  => 2,
+            "underset" => 2,
+            "stackrel" => 2,
+
+            // Chemistry
+            "ce" => 1,
+
+            // Physical units
+            "num"  => 1,
+            "si"   | "unit" => 1,
+            "SI"   | "qty"  => 2,
+
+            // Pre-scripts
+            "sideset"   => 3,
+            "prescript" => 3,
+
+            // Extensible arrows
+            "xrightarrow"     => 1,
+            "xleftarrow"      => 1,
+            "xleftrightarrow" => 1,
+            "xRightarrow"     => 1,
+            "xLeftarrow"      => 1,
+            "xLeftrightarrow" => 1,
+            "xmapsto"         => 1,
+            "xhookrightarrow" => 1,
+            "xhookleftarrow"  => 1,
 
             // Radical commands
             "sqrt" => 1,
@@ -274,6 +435,11 @@ impl PrimitiveControlSequence {
             " "     => 0,
             "quad"  => 0,
             "qquad" => 0,
+            "kern"   => 0,
+            "hskip"  => 0,
+            "hspace" => 1,
+            "mkern"  => 0,
+            "mskip"  => 0,
             "rule"  => 2,
 
 
@@ -284,11 +450,40 @@ impl PrimitiveControlSequence {
             "mathord" => 1,
 
             // Color related
-            "color"   => 2,
+            "color"   => 1,
+            "textcolor"   => 2,
+            "definecolor" => 3,
             "blue"    => 1,
             "red"     => 1,
             "gray"    => 1,
-            "phantom" => 1,
+
+            // Phantoms and overlaps
+            "phantom"  => 1,
+            "hphantom" => 1,
+            "vphantom" => 1,
+            "mathllap" => 1,
+            "mathrlap" => 1,
+            "mathclap" => 1,
+            "smash"    => 1,
+
+            // Frames
+            "boxed"     => 1,
+            "fbox"      => 1,
+            "colorbox"  => 2,
+            "fcolorbox" => 3,
+
+            // Modular arithmetic
+            "bmod" => 0,
+            "pmod" => 1,
+            "pod"  => 1,
+            "mod"  => 1,
+
+            // Style-dependent content
+            "mathchoice" => 4,
+
+            // Bold symbols
+            "boldsymbol" | "bm" => 1,
+            "pmb"               => 1,
 
             // Operators with limits
             "det"     => 0,
@@ -330,9 +525,20 @@ impl PrimitiveControlSequence {
             // Custom operators
             "operatorname" => 1,
 
+            // Cancellations
+            "cancel"   => 1,
+            "bcancel"  => 1,
+            "xcancel"  => 1,
+            "cancelto" => 2,
+
             // Text
             "underline" => 1,
             "text" => 1,
+            "textbf" => 1,
+            "textit" => 1,
+            "textrm" => 1,
+            "textsf" => 1,
+            "texttt" => 1,
             "mbox" => 1,
 
             // unsupported
@@ -342,6 +548,29 @@ impl PrimitiveControlSequence {
             _ => return None
         })
     }
+
+    /// Whether the command accepts an optional argument in square brackets before its
+    /// required arguments, e.g. `\sqrt[3]{x}`.
+    pub fn takes_optional_arg(command_name : &str) -> bool {
+        matches!(command_name,
+              "sqrt"
+            | "cfrac"
+            | "smash"
+            | "xrightarrow"
+            | "xleftarrow"
+            | "xleftrightarrow"
+            | "xRightarrow"
+            | "xLeftarrow"
+            | "xLeftrightarrow"
+            | "xmapsto"
+            | "xhookrightarrow"
+            | "xhookleftarrow"
+            | "color"
+            | "textcolor"
+            | "colorbox"
+            | "fcolorbox"
+        )
+    }
 }
 
 
@@ -372,10 +601,10 @@ pub enum SpaceKind {
 impl SpaceKind {
     pub fn size(self) -> AnyUnit {
         match self {
-            SpaceKind::NegativeThinMuSkip => AnyUnit::Em(-3f64/18f64),
-            SpaceKind::ThinSpace          => AnyUnit::Em(3f64/18f64),
-            SpaceKind::MedSpace           => AnyUnit::Em(4f64/18f64),
-            SpaceKind::ThickSpace         => AnyUnit::Em(5f64/18f64),
+            SpaceKind::NegativeThinMuSkip => AnyUnit::Mu(-3f64),
+            SpaceKind::ThinSpace          => AnyUnit::Mu(3f64),
+            SpaceKind::MedSpace           => AnyUnit::Mu(4f64),
+            SpaceKind::ThickSpace         => AnyUnit::Mu(5f64),
             SpaceKind::WordSpace          => AnyUnit::Em(1f64/4f64),
             SpaceKind::QuadSpace          => AnyUnit::Em(1.0f64),
             SpaceKind::DoubleQuadSpace    => AnyUnit::Em(2.0f64),
@@ -385,16 +614,38 @@ impl SpaceKind {
 
 
 
-pub fn parse_color<'a, I : Iterator<Item = TexToken<'a>>>(token_iter : I) -> ParseResult<RGBA> {
+/// Parses the argument of commands like `\color{..}`, given in the color model of the optional argument if any (e.g. `\color[rgb]{0.1,0.2,0.3}`). 
+/// Cf [`RGBA::from_str`](std::str::FromStr::from_str) and [`RGBA::from_model`] for the syntax of colors ; without a model, 
+/// the names defined in `palette` can be used.
+pub fn parse_color<'a, I : Iterator<Item = TexToken<'a>>>(model : Option<&str>, palette : &ColorPalette, token_iter : I) -> ParseResult<RGBA> {
     let mut color_name = String::with_capacity("#11223344".len()); // #rrggbbaa, preparing for the worst case
     for token in token_iter {
         match token {
             TexToken::Char(c) => color_name.push(c),
-            TexToken::ControlSequence(_) => todo!(),
-            _ => todo!()
+            TexToken::WhiteSpace => color_name.push(' '),
+            // `#123456` is read as a macro parameter
+            TexToken::Argument(number) => {
+                color_name.push('#');
+                color_name.push_str(&number.to_string());
+            },
+            TexToken::BeginGroup | TexToken::EndGroup => (),
+              TexToken::ControlSequence(_)
+            | TexToken::Superscript
+            | TexToken::Subscript
+            | TexToken::Alignment
+            | TexToken::Tilde
+            | TexToken::Prime(_)
+            => return Err(ParseError::ExpectedChars),
         }
     }
-    let color : RGBA = color_name.parse().map_err(|_| ParseError::UnrecognizedColor(color_name.into_boxed_str()))?;
-    Ok(color)
+
+    let color = match model {
+        Some(model) => RGBA::from_model(model, &color_name),
+        None        => palette.evaluate(color_name.trim()),
+    };
+    color.map_err(|error| match error {
+        ColorParseError::UnknownColorModel => ParseError::UnrecognizedColorModel(Box::from(model.unwrap_or_default())),
+        _ => ParseError::UnrecognizedColor(Box::from(color_name.trim())),
+    })
 }
 
@@ -8,10 +8,12 @@ use crate::layout;
 use crate::parser::error::ParseError;
 use crate::parser::List;
 
-use super::nodes::{Array, ArrayColumnAlign, ArrayColumnsFormatting, ColSeparator, DummyNode};
+use crate::dimensions::AnyUnit;
+use super::nodes::{Array, ArrayColumnAlign, ArrayColumnsFormatting, ArrayRowSpacing, ColSeparator, DummyNode, RowSeparator};
+use super::control_sequence::SpaceKind;
 use super::symbols::Symbol;
 use super::{error::ParseResult, nodes::CellContent, textoken::TexToken, Parser};
-use super::{GroupKind, ParseNode};
+use super::{GroupKind, ParseNode, tokens_as_string};
 
 /// An enumeration of recognized environments.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -39,20 +41,47 @@ pub enum Environment {
     /// The `\begin{aligned} .. \end{aligned}` environment, an array with alternating right-aligned and left-aligned column, not separated with spaces.  
     ///  Except for spacing, equivalent to `\left\lVert\begin{array}{rlrl .. rl} .. \end{array}\right\rVert` 
     Aligned,
+    /// The `\begin{cases} .. \end{cases}` environment, two left-aligned columns in text style, preceded by a curly brace.  
+    ///  Except for spacing, equivalent to `\left\{\begin{array}{@{}l@{\quad}l@{}} .. \end{array}\right.` 
+    Cases,
+    /// The `\begin{dcases} .. \end{dcases}` environment, like [`Environment::Cases`] but cells are laid out in display style.
+    DCases,
+    /// The `\begin{rcases} .. \end{rcases}` environment, like [`Environment::Cases`] but the curly brace is on the right.  
+    ///  Except for spacing, equivalent to `\left.\begin{array}{@{}l@{\quad}l@{}} .. \end{array}\right\}` 
+    RCases,
+    /// The `\begin{gathered} .. \end{gathered}` environment, a single column of centered lines laid out in display style.
+    Gathered,
+    /// The `\begin{alignedat}{n} .. \end{alignedat}` environment, like [`Environment::Aligned`] with `n` pairs of right-aligned and left-aligned columns, 
+    /// but without any space between the pairs.
+    AlignedAt,
+    /// The `\begin{split} .. \end{split}` environment, like [`Environment::Aligned`] but with a single pair of right-aligned and left-aligned columns.
+    Split,
+    /// The `\begin{smallmatrix} .. \end{smallmatrix}` environment, a matrix with centered cells laid out in script style and tight spacing, suitable for inline formulas.
+    SmallMatrix,
 }
 
+/// Horizontal lines above each row of an array, cf [`Array::row_separators`]
+type RowSeparators = Vec<Vec<RowSeparator>>;
+
 impl Environment {
     /// Parse the LaTeX name of the environment. Returns `None` if the name is not an environment we support.
     pub fn from_name(name : &str) ->  Option<Self> {
         match name {
-            "array"    => Some(Self::Array),
-            "matrix"   => Some(Self::Matrix),
-            "pmatrix"  => Some(Self::PMatrix),
-            "bmatrix"  => Some(Self::BMatrix),
-            "Bmatrix"  => Some(Self::BbMatrix),
-            "vmatrix"  => Some(Self::VMatrix),
-            "Vmatrix"  => Some(Self::VvMatrix),
-            "aligned"  => Some(Self::Aligned),
+            "array"       => Some(Self::Array),
+            "matrix"      => Some(Self::Matrix),
+            "pmatrix"     => Some(Self::PMatrix),
+            "bmatrix"     => Some(Self::BMatrix),
+            "Bmatrix"     => Some(Self::BbMatrix),
+            "vmatrix"     => Some(Self::VMatrix),
+            "Vmatrix"     => Some(Self::VvMatrix),
+            "aligned"     => Some(Self::Aligned),
+            "cases"       => Some(Self::Cases),
+            "dcases"      => Some(Self::DCases),
+            "rcases"      => Some(Self::RCases),
+            "gathered"    => Some(Self::Gathered),
+            "alignedat"   => Some(Self::AlignedAt),
+            "split"       => Some(Self::Split),
+            "smallmatrix" => Some(Self::SmallMatrix),
             _ => None
         }
     }
@@ -75,7 +104,24 @@ impl<'a, I : Iterator<Item = TexToken<'a>>> Parser<'a, I> {
             let mut forked_parser = Parser::from_iter(Self::EMPTY_COMMAND_COLLECTION, group.into_iter());
             col_format = Some(forked_parser.tokens_as_column_format()?);
         }
-        let mut rows = self.parse_array_body(env)?;
+
+        let mut n_column_pairs = 0;
+        if let Environment::AlignedAt = env {
+            let group = self.token_iter
+                .capture_group()
+                .map_err(|e| match e {
+                    ParseError::ExpectedToken => ParseError::ExpectedColumnCountForAlignedAtEnvironment,
+                    _ => e,
+                })?;
+
+            n_column_pairs = tokens_as_string(group.into_iter())?
+                .trim()
+                .parse::<usize>()
+                .ok()
+                .filter(|n_column_pairs| *n_column_pairs > 0)
+                .ok_or(ParseError::ExpectedColumnCountForAlignedAtEnvironment)?;
+        }
+        let (mut rows, row_separators) = self.parse_array_body(env)?;
 
         let left_delimiter;
         let right_delimiter;
@@ -83,7 +129,11 @@ impl<'a, I : Iterator<Item = TexToken<'a>>> Parser<'a, I> {
         match env {
             Environment::Array   |
             Environment::Matrix  | 
-            Environment::Aligned
+            Environment::Aligned |
+            Environment::Gathered  |
+            Environment::AlignedAt |
+            Environment::Split     |
+            Environment::SmallMatrix
             => {
                 left_delimiter  = None;
                 right_delimiter = None;
@@ -108,11 +158,19 @@ impl<'a, I : Iterator<Item = TexToken<'a>>> Parser<'a, I> {
                 left_delimiter  = Some(Symbol {codepoint : '\u{2016}', atom_type : TexSymbolType::Inner});
                 right_delimiter = Some(Symbol {codepoint : '\u{2016}', atom_type : TexSymbolType::Inner});
             },
+            Environment::Cases | Environment::DCases => {
+                left_delimiter  = Some(Symbol {codepoint : '{', atom_type : TexSymbolType::Inner});
+                right_delimiter = None;
+            },
+            Environment::RCases => {
+                left_delimiter  = None;
+                right_delimiter = Some(Symbol {codepoint : '}', atom_type : TexSymbolType::Inner});
+            },
         }
 
-        // For the `aligned` ennvironment, we add dummies in even columns (second, fourth, etc.)
+        // For the `aligned` ennvironment (and its variants), we add dummies in even columns (second, fourth, etc.)
         // which copy the atom_type of the last node of the previous column
-        if let Environment::Aligned = env {
+        if let Environment::Aligned | Environment::AlignedAt | Environment::Split = env {
             for row in rows.iter_mut() {
                 for cell in row.chunks_exact_mut(2) {
                     let atom_type = cell[0].last().map_or_else(
@@ -132,6 +190,41 @@ impl<'a, I : Iterator<Item = TexToken<'a>>> Parser<'a, I> {
                     separators: [Vec::new(), vec![ColSeparator::AtExpression(Vec::new())]].iter().cycle().cloned().take(n_cols + 1).collect(),
                 }
             }
+            else if let Environment::Cases | Environment::DCases | Environment::RCases = env {
+                // Corresponds to the column specification `@{}l@{\quad}l@{}`
+                let quad = ParseNode::Kerning(SpaceKind::QuadSpace.size());
+                ArrayColumnsFormatting {
+                    alignment:  vec![ArrayColumnAlign::Left; 2],
+                    separators: vec![
+                        vec![ColSeparator::AtExpression(Vec::new())],
+                        vec![ColSeparator::AtExpression(vec![quad])],
+                        vec![ColSeparator::AtExpression(Vec::new())],
+                    ],
+                }
+            }
+            else if let Environment::AlignedAt = env {
+                // Pairs of columns are not separated by any space: `r@{}l@{}r@{}l .. r@{}l`
+                let n_cols = 2 * n_column_pairs;
+                let mut separators = vec![Vec::new()];
+                separators.extend(vec![vec![ColSeparator::AtExpression(Vec::new())]; n_cols.saturating_sub(1)]);
+                separators.push(Vec::new());
+                ArrayColumnsFormatting {
+                    alignment:  [ArrayColumnAlign::Right, ArrayColumnAlign::Left].iter().cycle().cloned().take(n_cols).collect(),
+                    separators,
+                }
+            }
+            else if let Environment::Split = env {
+                ArrayColumnsFormatting {
+                    alignment:  vec![ArrayColumnAlign::Right, ArrayColumnAlign::Left],
+                    separators: vec![Vec::new(), vec![ColSeparator::AtExpression(Vec::new())], Vec::new()],
+                }
+            }
+            else if let Environment::Gathered = env {
+                ArrayColumnsFormatting {
+                    alignment:  vec![ArrayColumnAlign::Centered],
+                    separators: vec![Vec::new(), Vec::new()],
+                }
+            }
             else {
                 ArrayColumnsFormatting { 
                     alignment:  vec![ArrayColumnAlign::Centered; n_cols], 
@@ -140,36 +233,70 @@ impl<'a, I : Iterator<Item = TexToken<'a>>> Parser<'a, I> {
             }
         });
 
+        // `\cline{i-j}` must stay within the columns of the array
+        let n_cols = col_format.alignment.len();
+        for separator in row_separators.iter().flatten() {
+            if let RowSeparator::PartialLine { last, .. } = *separator {
+                if last > n_cols {
+                    return Err(ParseError::ColumnRangeOutOfBounds { last, n_cols });
+                }
+            }
+        }
+
         let extra_row_sep = match env {
-            Environment::Aligned => true,
+            Environment::Aligned | Environment::Gathered | Environment::AlignedAt 
+            | Environment::Split 
+            => true,
             Environment::Array | Environment::Matrix | Environment::PMatrix 
             | Environment::BMatrix | Environment::BbMatrix | Environment::VMatrix 
-            | Environment::VvMatrix 
+            | Environment::VvMatrix | Environment::Cases | Environment::DCases 
+            | Environment::RCases | Environment::SmallMatrix 
             => false,
         };
 
         let cell_layout_style = match env {
-            Environment::Aligned => layout::Style::Display,
+            Environment::Aligned | Environment::DCases | Environment::Gathered 
+            | Environment::AlignedAt | Environment::Split 
+            => layout::Style::Display,
             Environment::Array | Environment::Matrix | Environment::PMatrix 
             | Environment::BMatrix | Environment::BbMatrix | Environment::VMatrix 
-            | Environment::VvMatrix 
+            | Environment::VvMatrix | Environment::Cases | Environment::RCases 
             => layout::Style::Text,
+            Environment::SmallMatrix => layout::Style::Script,
+        };
+
+        // Values from the definition of `smallmatrix` in amsmath: 
+        // columns are separated by `\thickspace`, `\baselineskip` is 6\ex@ and `\lineskip` is 1.5\ex@ (\ex@ being .2326ex, roughly 0.1em)
+        let (half_col_sep, row_spacing) = match env {
+            Environment::SmallMatrix => (
+                Some(AnyUnit::Em(5. / 36.)),
+                Some(ArrayRowSpacing { 
+                    baseline_skip: AnyUnit::Em(0.6), 
+                    line_skip:     AnyUnit::Em(0.15),
+                }),
+            ),
+            _ => (None, None),
         };
 
         Ok(Array {
             col_format,
             rows,
+            row_separators,
             left_delimiter,
             right_delimiter,
             extra_row_sep,
             cell_layout_style,
+            half_col_sep,
+            row_spacing,
         })
     }
 
     /// Parses the body of an array environment up till the `\end{..}`, including it.
-    pub fn parse_array_body(&mut self, env : Environment) -> ParseResult<Vec<Vec<CellContent>>> {
+    /// Returns the rows of the array along with the horizontal lines that separate them (cf [`Array::row_separators`]).
+    pub fn parse_array_body(&mut self, env : Environment) -> ParseResult<(Vec<Vec<CellContent>>, RowSeparators)> {
         let mut to_return    = Vec::new();
         let mut current_line = Vec::new();
+        let mut row_separators = vec![self.parse_row_separators()?];
 
         while {
             let List {nodes, group} = self.parse_until_end_of_group()?;
@@ -189,6 +316,7 @@ impl<'a, I : Iterator<Item = TexToken<'a>>> Parser<'a, I> {
                 GroupKind::NewLine => {
                     current_line.push(nodes);
                     to_return.push(std::mem::take(&mut current_line));
+                    row_separators.push(self.parse_row_separators()?);
                     true
                 },
 
@@ -197,8 +325,34 @@ impl<'a, I : Iterator<Item = TexToken<'a>>> Parser<'a, I> {
         }
         {}
 
+        // If the last row is not followed by `\\`, there are no lines below it
+        row_separators.resize(to_return.len() + 1, Vec::new());
+
+        Ok((to_return, row_separators))
+    }
 
-        Ok(to_return)
+    /// Parses the horizontal lines (`\hline`, `\cline{..}`, etc.) found at the beginning of a row.
+    fn parse_row_separators(&mut self) -> ParseResult<Vec<RowSeparator>> {
+        let mut separators = Vec::new();
+        loop {
+            match self.token_iter.peek_token()? {
+                Some(TexToken::WhiteSpace) => (),
+                Some(TexToken::ControlSequence("hline"))     => separators.push(RowSeparator::HorizontalLine { dashed: false }),
+                Some(TexToken::ControlSequence("hdashline")) => separators.push(RowSeparator::HorizontalLine { dashed: true }),
+                Some(TexToken::ControlSequence(name @ ("cline" | "cdashline"))) => {
+                    self.token_iter.next_token()?;
+                    let range = self.token_iter.capture_group().map_err(|e| match e {
+                        ParseError::ExpectedToken => ParseError::ExpectedColumnRange,
+                        _ => e,
+                    })?;
+                    let (first, last) = parse_column_range(&tokens_as_string(range.into_iter())?)?;
+                    separators.push(RowSeparator::PartialLine { first, last, dashed: name == "cdashline" });
+                    continue;
+                },
+                _ => return Ok(separators),
+            }
+            self.token_iter.next_token()?;
+        }
     }
 }
 
@@ -264,6 +418,18 @@ impl<'a, I : Iterator<Item = TexToken<'a>>> Parser<'a, I> {
     }
 }
 
+/// Parses the argument of `\cline`, e.g. "2-3".
+fn parse_column_range(range : &str) -> ParseResult<(usize, usize)> {
+    let (first, last) = range.split_once('-').ok_or(ParseError::ExpectedColumnRange)?;
+    let first = first.trim().parse::<usize>().map_err(|_| ParseError::ExpectedColumnRange)?;
+    let last  = last.trim().parse::<usize>().map_err(|_| ParseError::ExpectedColumnRange)?;
+
+    if first == 0 || last < first {
+        return Err(ParseError::ExpectedColumnRange);
+    }
+    Ok((first, last))
+}
+
 #[cfg(test)]
 mod tests {
     use crate::parser::macros::CommandCollection;
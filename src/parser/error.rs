@@ -22,7 +22,13 @@ pub enum ParseError {
     ///  - #RRGGBB (ie: `#ff0000` for red)
     ///  - #RRGGBBAA (ie: `#00000000` for transparent)
     ///  - `transparent`
+    ///  - `rgb(r, g, b)` and `rgba(r, g, b, a)`
+    ///  - mix expressions like `red!30!blue`
+    ///
+    /// With a color model (e.g. `\color[rgb]{..}`), the argument must be the specification of a color in this model.
     UnrecognizedColor(Box<str>),
+    /// The color model in the optional argument of commands like `\color[..]{..}` is not one of `rgb`, `RGB`, `HTML`, `gray`, `cmyk` and `cmy`.
+    UnrecognizedColorModel(Box<str>),
     /// A custom macro is missing an argument
     MissingArgForMacro {
         /// Expected number of arguments
@@ -32,6 +38,8 @@ pub enum ParseError {
     },
     /// The brackets used to enclose a macro's arguments were not matched
     UnmatchedBrackets,
+    /// Math in text (e.g. the `$x$` of `\text{if $x > 0$}`) is opened but never closed
+    UnterminatedInlineMath,
     /// A group (e.g. `{..}`, `\begin{env}..\end{env}`, `&...&`) was ended but there is no correponding begin group
     UnexpectedEndGroup{ 
         /// The kind of group endings that were expected in that position
@@ -47,11 +55,23 @@ pub enum ParseError {
     MissingArgForCommand(Box<str>),
     /// `\begin{array}` must be followed by a group describing column format (e.g. `\begin{array}{cc}`)
     MissingColFormatForArrayEnvironment,
+    /// `\begin{alignedat}` must be followed by a group containing the number of column pairs (e.g. `\begin{alignedat}{2}`)
+    ExpectedColumnCountForAlignedAtEnvironment,
+    /// `\cline` must be followed by a range of columns (e.g. `\cline{2-3}`)
+    ExpectedColumnRange,
+    /// The range of columns given to `\cline` goes past the last column of the array (e.g. `\cline{2-3}` in a two-column array)
+    ColumnRangeOutOfBounds {
+        /// Last column of the range
+        last : usize,
+        /// Number of columns in the array
+        n_cols : usize,
+    },
     /// After `^` and `_`, a group or a token must follow.
     MissingSubSuperScript,
     /// There either is more than one subscript or more than one superscript attached to the same node.
     TooManySubscriptsOrSuperscripts,
-    /// The command `\rule` expects an argument of the form `1.3pt` (number followed by dimension). The dimension may not be anything but `em` or `pt` at the moment.
+    /// Commands like `\rule` or `\kern` expect an argument of the form `1.3pt` (number followed by dimension). 
+    /// The dimension may be any of `em`, `ex`, `mu`, `px`, `pt`, `bp`, `pc`, `dd`, `sp`, `cm`, `mm` or `in`.
     UnrecognizedDimension(Box<str>),
     /// The string in `\begin{..}` or `\end{..}` is not a recognized environment. Cf [Environment] for the list of supported LaTeX environments.
     UnrecognizedEnvironment(Box<str>),
@@ -73,6 +93,8 @@ pub enum ParseError {
     LimitControlSequenceMustBeAfterOperator,
     /// In parsing a style file, the parser expected a `\newcommand` but either got end of input or a different type of token.
     ExpectedNewCommand,
+    /// In parsing a color palette, the parser expected a `\definecolor` but got something else.
+    ExpectedColorDefinition,
     /// In parsing a formula, the parser encountered an argument like #123 as used in macro definition, but it wasn't in a macro definition environment.
     UnexpectedMacroArgument,
     /// The number following '#' in the macro parameter is 0. Macro parameter numbers start with 1.
@@ -83,6 +105,36 @@ pub enum ParseError {
     MoreArgsThanSpecified,
     /// In the first argument of `\newcommand`, expected the name of a command.
     ExpectedMacroName,
+    /// `\newcommand` was used on a macro which is already defined ; use `\renewcommand` instead.
+    MacroAlreadyDefined(Box<str>),
+    /// `\renewcommand` was used on a command which isn't defined.
+    MacroNotDefined(Box<str>),
+    /// The parameter text of `\def` may only contain parameters in increasing order (e.g. `\def\foo#1#2{..}`) ; delimited parameters are not supported.
+    UnsupportedParameterText,
+    /// In the macro definition `\newcommand{..}[n][default]{..}`, a default value for the first argument was given but `n` is 0.
+    DefaultArgWithoutArgs,
+    /// Too many macros were expanded, most likely because a macro is defined in terms of itself (e.g. `\def\x{\x}\x`).
+    TooManyMacroExpansions,
+    /// The optional argument of `\smash[..]{..}` is neither `t` nor `b`.
+    UnrecognizedSmashOption(Box<str>),
+    /// `\mkern` and `\mskip` were given a dimension which is not in `mu` (e.g. `\mkern3pt`).
+    ExpectedMathUnit(Box<str>),
+    /// A dimension in `mu` was given to a command other than `\mkern` and `\mskip` (e.g. `\kern3mu`).
+    UnexpectedMathUnit(Box<str>),
+    /// The style argument of `\genfrac{..}{..}{..}{..}{..}{..}` is neither empty nor one of `0`, `1`, `2` and `3`.
+    UnrecognizedFractionStyle(Box<str>),
+    /// The optional argument of `\cfrac[..]{..}{..}` is neither `l`, `c` nor `r`.
+    UnrecognizedFractionAlignment(Box<str>),
+    /// More than one of `\over`, `\atop` and `\choose` was used in the same group (e.g. `a \over b \over c`).
+    AmbiguousInfixFraction,
+    /// The first two arguments of `\sideset{..}{..}` may only contain a subscript and/or a superscript (e.g. `\sideset{_a^b}{^c}\sum`).
+    ExpectedScriptsForSideset,
+    /// The argument of `\num{..}` or the first argument of `\SI{..}{..}` is not a number (e.g. `1.5e-3`, `2x3` or `1.2+-0.1`).
+    UnrecognizedNumber(Box<str>),
+    /// A control sequence in the argument of `\si{..}` is neither a unit (e.g. `\metre`), a prefix (e.g. `\kilo`) nor a power (e.g. `\squared`).
+    UnrecognizedUnit(Box<str>),
+    /// In the argument of `\si{..}`, a prefix, a power or `\per` is not attached to any unit (e.g. `\si{\kilo}` or `\si{\squared}`).
+    ExpectedUnit,
 }
 
 
@@ -96,10 +148,14 @@ impl fmt::Display for ParseError {
                 write!(f, "Unknown control sequence '\\{}'", control_seq),
             UnrecognizedColor(color_arg) => 
                 write!(f, "'{}' is not a recognized color", color_arg),
+            UnrecognizedColorModel(model) => 
+                write!(f, "'{}' is not a recognized color model", model),
             MissingArgForMacro { expected, got } => 
                 write!(f, "Expected {} arguments for custom macros, got {}", expected, got),
             UnmatchedBrackets => 
                 write!(f, "Macro arguments has unmatched bracket"),
+            UnterminatedInlineMath => 
+                write!(f, "Math in text is not terminated"),
             UnexpectedEndGroup { expected, got } => {
                 let expecteds = expected
                     .into_iter()
@@ -117,6 +173,12 @@ impl fmt::Display for ParseError {
                 write!(f, "One argument is missing for '\\{}'", control_seq),
             MissingColFormatForArrayEnvironment => 
                 write!(f, "Column format is missing for \\begin{{array}}"),
+            ExpectedColumnCountForAlignedAtEnvironment => 
+                write!(f, "Number of column pairs is missing or invalid for \\begin{{alignedat}}"),
+            ExpectedColumnRange => 
+                write!(f, "Expected a range of columns like '2-3' after \\cline"),
+            ColumnRangeOutOfBounds { last, n_cols } => 
+                write!(f, "\\cline reaches column {} but the array only has {} columns", last, n_cols),
             MissingSubSuperScript => 
                 write!(f, "Missing group after _ or ^"),
             TooManySubscriptsOrSuperscripts => 
@@ -141,6 +203,8 @@ impl fmt::Display for ParseError {
                 write!(f, r"'\limits' or '\nolimits' isn't placed after an operator"),
             ExpectedNewCommand => 
                 write!(f, r"Expected a '\newcommand'"),
+            ExpectedColorDefinition => 
+                write!(f, r"Expected a '\definecolor'"),
             UnexpectedMacroArgument =>
                 write!(f, r"Unexpected macro argument"),
             IllegalParameterNumber =>
@@ -151,6 +215,36 @@ impl fmt::Display for ParseError {
                 write!(f, r"Macro parameter number is higher than the number of arguments listed in macro"),
             ExpectedMacroName =>
                 write!(f, r"First argument of \newcommand should be the name of a macro"),
+            MacroAlreadyDefined(name) =>
+                write!(f, r"Macro '\{}' is already defined, use \renewcommand to redefine it", name),
+            MacroNotDefined(name) =>
+                write!(f, r"Macro '\{}' is not defined and cannot be redefined with \renewcommand", name),
+            UnsupportedParameterText =>
+                write!(f, r"Parameter text of \def should be of the form #1#2..."),
+            DefaultArgWithoutArgs =>
+                write!(f, r"A default value for the first argument was given to a macro without arguments"),
+            TooManyMacroExpansions =>
+                write!(f, r"Too many macro expansions, a macro may be defined recursively"),
+            UnrecognizedSmashOption(option) =>
+                write!(f, r"Optional argument of \smash should be 't' or 'b', got '{}'", option),
+            ExpectedMathUnit(control_seq) =>
+                write!(f, r"'\{}' expects a dimension in 'mu'", control_seq),
+            UnexpectedMathUnit(control_seq) =>
+                write!(f, r"'\{}' does not accept dimensions in 'mu'", control_seq),
+            UnrecognizedFractionStyle(style) =>
+                write!(f, r"Style argument of \genfrac should be empty or one of 0, 1, 2, 3, got '{}'", style),
+            UnrecognizedFractionAlignment(alignment) =>
+                write!(f, r"Optional argument of \cfrac should be 'l', 'c' or 'r', got '{}'", alignment),
+            AmbiguousInfixFraction =>
+                write!(f, r"Ambiguous fraction: only one of \over, \atop or \choose may be used per group"),
+            ExpectedScriptsForSideset =>
+                write!(f, r"Arguments of \sideset should only contain scripts, e.g. '_a^b'"),
+            UnrecognizedNumber(number) =>
+                write!(f, "'{}' is not a recognized number", number),
+            UnrecognizedUnit(unit) =>
+                write!(f, "'\\{}' is not a recognized unit", unit),
+            ExpectedUnit =>
+                write!(f, "Expected a unit after a prefix, a power or '\\per'"),
         }
     }
 }
@@ -43,16 +43,20 @@ impl CommandCollection {
     where I: Iterator<Item = TexToken<'a>> 
     {
         let mut definitions = Vec::new();
-        while token_iter.peek_token()? != None {
+        // Definitions must not be picked up by the iterator, so we only look at unexpanded tokens here
+        while let Some(token) = token_iter.peek_unexpanded_non_whitespace_token() {
+            let kind = match token {
+                TexToken::ControlSequence(name) => DefinitionKind::from_name(name),
+                _ => None,
+            };
             let definition = CustomCommand::parse_macro_definition_from_iter(token_iter)?;
-            definitions.push(definition);
-
-            // Consume whitespace
-            let mut token = token_iter.peek_token()?;
-            while let Some(TexToken::WhiteSpace) = token {
-                token_iter.next_token()?;
-                token = token_iter.peek_token()?;
+            let is_macro = definitions.iter().any(|command : &CustomCommand| command.name() == definition.name());
+            if let Some(kind) = kind {
+                kind.check_definition(definition.name(), is_macro)?;
             }
+            // A later definition (e.g. with `\renewcommand`) overrides an earlier one
+            definitions.retain(|command : &CustomCommand| command.name() != definition.name());
+            definitions.push(definition);
         }
         Ok(Self(definitions))
     }
@@ -68,6 +72,17 @@ enum CommandToken {
 }
 
 
+impl CommandToken {
+    fn as_tex_token(&self) -> TexToken<'_> {
+        match self {
+            Self::NormalToken(token) => token.clone(),
+            Self::OwnedCommand(name) => TexToken::ControlSequence(name),
+            Self::ArgSlot(i)         => TexToken::Argument(i + 1),
+        }
+    }
+}
+
+
 #[derive(Debug, Clone)]
 enum TokenConversionError {
     IllegalParameterNumber
@@ -114,6 +129,8 @@ pub struct CustomCommand {
 
     // !! This should all be private
     expansion : Vec<CommandToken>,
+    /// Value of the first argument when it is optional and omitted, as in `\newcommand{\foo}[2][default]{..}`
+    default_arg : Option<Vec<CommandToken>>,
 }
 
 struct ExpansionIterator<'args, 'token> {
@@ -157,7 +174,7 @@ impl<'arg, 'a> Iterator for ExpansionIterator<'arg, 'a> {
 impl CustomCommand {
     /// A macro that does expands to nothing, as could be defined with `\newcommand{\emptycommand}[n]{}`.
     pub fn empty_command(name : &str, n_args : usize) -> Self {
-        Self { n_args, name: name.to_string(), expansion: Vec::new() }
+        Self { n_args, name: name.to_string(), expansion: Vec::new(), default_arg: None }
     }
 
     /// Number of arguments required for macro expansion.
@@ -165,6 +182,17 @@ impl CustomCommand {
         self.n_args
     }
 
+    /// Whether the first argument is optional, i.e. given in brackets at call site, as defined with `\newcommand{\foo}[n][default]{..}`.
+    pub fn has_optional_arg(&self) -> bool {
+        self.default_arg.is_some()
+    }
+
+    fn default_arg<'token>(& 'token self) -> Option<Vec<TexToken<'token>>> {
+        self.default_arg.as_ref().map(|tokens| 
+            tokens.iter().map(CommandToken::as_tex_token).collect()
+        )
+    }
+
     fn expand_iter<'args, 'token>(& 'token self, args : & 'args [Vec<TexToken<'token>>]) -> ExpansionIterator<'args, 'token> {
         let Self { expansion, .. } = self;
         ExpansionIterator { 
@@ -183,70 +211,199 @@ impl CustomCommand {
     }
 
     fn parse_macro_definition_from_iter<'a, I : Iterator<Item = TexToken<'a>>>(token_iter : &mut ExpandedTokenIter<'a, I>) -> ParseResult<Self> {
-        let mut token = token_iter.next_token()?;
-        while let Some(TexToken::WhiteSpace) = token {
-            token = token_iter.next_token()?;
+        let kind = match token_iter.next_unexpanded_non_whitespace_token() {
+            Some(TexToken::ControlSequence(name)) => DefinitionKind::from_name(name),
+            _ => None,
+        }.ok_or(ParseError::ExpectedNewCommand)?;
+
+        let local_command = LocalCommand::parse_definition_from_iter(kind, token_iter)?;
+        Ok(Self::try_from(local_command)?)
+    }
+
+}
+
+impl<'a> TryFrom<LocalCommand<'a>> for CustomCommand {
+    type Error = TokenConversionError;
+
+    fn try_from(value: LocalCommand<'a>) -> Result<Self, Self::Error> {
+        let LocalCommand { n_args, name, expansion, default_arg } = value;
+        let expansion = expansion
+            .into_iter()
+            .map(CommandToken::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        let default_arg = default_arg
+            .map(|tokens| tokens.into_iter().map(CommandToken::try_from).collect::<Result<Vec<_>, _>>())
+            .transpose()?;
+        Ok(Self { n_args, name: name.to_string(), expansion, default_arg })
+    }
+}
+
+/// The different ways of defining a macro.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DefinitionKind {
+    /// `\newcommand{\foo}[n]{..}`, fails if `\foo` is already a macro
+    NewCommand,
+    /// `\renewcommand{\foo}[n]{..}`, fails if `\foo` is not defined
+    RenewCommand,
+    /// `\def\foo#1#2{..}`, defines `\foo` unconditionally
+    Def,
+    /// `\DeclareMathOperator{\foo}{foo}`, a shorthand for `\newcommand{\foo}{\operatorname{foo}}`
+    DeclareMathOperator,
+}
+
+impl DefinitionKind {
+    fn from_name(name : &str) -> Option<Self> {
+        match name {
+            "newcommand"   => Some(Self::NewCommand),
+            "renewcommand" => Some(Self::RenewCommand),
+            "def"          => Some(Self::Def),
+            "DeclareMathOperator" => Some(Self::DeclareMathOperator),
+            _ => None,
         }
+    }
 
-        match token {
-            Some(TexToken::ControlSequence("newcommand")) => (),
-            _ => {
-                return Err(ParseError::ExpectedNewCommand);
-            },
+    /// Checks that a macro named `name` may be defined this way, given whether it is already a macro: 
+    /// like in LaTeX, `\newcommand` fails on macros and primitives (e.g. `\frac`) while `\renewcommand` fails on anything else.
+    fn check_definition(self, name : &str, is_macro : bool) -> ParseResult<()> {
+        let is_defined = is_macro || PrimitiveControlSequence::from_name(name).is_some();
+        match self {
+            Self::NewCommand | Self::DeclareMathOperator if is_defined =>
+                Err(ParseError::MacroAlreadyDefined(Box::from(name))),
+            Self::RenewCommand if !is_defined =>
+                Err(ParseError::MacroNotDefined(Box::from(name))),
+            _ => Ok(()),
         }
+    }
+}
+
+/// A macro defined inside the formula being parsed (e.g. `$\def\R{\mathbb{R}} \R^n$`).
+/// Unlike [`CustomCommand`], it borrows its name and tokens from the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LocalCommand<'a> {
+    n_args : usize,
+    name : &'a str,
+    /// Macro parameters are left as [`TexToken::Argument`]
+    expansion : Vec<TexToken<'a>>,
+    default_arg : Option<Vec<TexToken<'a>>>,
+}
 
+impl<'a> LocalCommand<'a> {
+    fn expand(&self, args : &[Vec<TexToken<'a>>]) -> Vec<TexToken<'a>> {
+        let mut expanded_tokens = Vec::with_capacity(self.expansion.len());
+        for token in self.expansion.iter() {
+            match token {
+                TexToken::Argument(i) => expanded_tokens.extend(args[i - 1].iter().cloned()),
+                token                 => expanded_tokens.push(token.clone()),
+            }
+        }
+        expanded_tokens
+    }
 
-        let group = token_iter.capture_group()?;
-        let name = match group[..] {
-            [TexToken::ControlSequence(name)] => name,
-            _ => return Err(ParseError::ExpectedMacroName),
+    /// Parses what follows `\newcommand`, `\renewcommand`, `\def` or `\DeclareMathOperator`.
+    fn parse_definition_from_iter<I : Iterator<Item = TexToken<'a>>>(kind : DefinitionKind, token_iter : &mut ExpandedTokenIter<'a, I>) -> ParseResult<Self> {
+        // The starred variants of `\newcommand` and `\renewcommand` only differ from the regular ones with respect to paragraphs,
+        // while `\DeclareMathOperator*` declares an operator with limits.
+        let starred = match kind {
+            DefinitionKind::Def => false,
+            _ => {
+                let starred = token_iter.peek_unexpanded_non_whitespace_token() == Some(TexToken::Char('*'));
+                if starred {
+                    token_iter.produce_next_token();
+                }
+                starred
+            },
         };
 
+        let name = token_iter.capture_macro_name()?;
 
-        let group = token_iter.capture_optional_group()?;
+        let (n_args, default_arg) = match kind {
+            // `\newcommand{\foo}[n][default]{..}`
+            DefinitionKind::NewCommand | DefinitionKind::RenewCommand => {
+                let group = token_iter.capture_optional_group()?;
+                let n_args = 
+                    if let Some(n_arg_group) = group {
+                        let n_args_string = tokens_as_string(n_arg_group.into_iter())?;
+                        str::parse::<usize>(&n_args_string).map_err(|_| ParseError::ExpectedNumber)?
+                    }
+                    else 
+                    { 0 }
+                ;
 
+                let default_arg = token_iter.capture_optional_group()?;
+                if default_arg.is_some() && n_args == 0 {
+                    return Err(ParseError::DefaultArgWithoutArgs);
+                }
+                if let Some(default_arg) = &default_arg {
+                    if default_arg.iter().any(|token| matches!(token, TexToken::Argument(_))) {
+                        return Err(ParseError::UnexpectedMacroArgument);
+                    }
+                }
+                (n_args, default_arg)
+            },
+            // `\def\foo#1#2{..}`
+            DefinitionKind::Def => {
+                let mut n_args = 0;
+                loop {
+                    match token_iter.peek_unexpanded_non_whitespace_token() {
+                        Some(TexToken::Argument(i)) if i == n_args + 1 => n_args += 1,
+                        Some(TexToken::Argument(_)) => return Err(ParseError::IllegalParameterNumber),
+                        Some(TexToken::BeginGroup)  => break,
+                        _ => return Err(ParseError::UnsupportedParameterText),
+                    }
+                    token_iter.produce_next_token();
+                }
+                (n_args, None)
+            },
+            DefinitionKind::DeclareMathOperator => (0, None),
+        };
 
-        let n_args : usize = 
-            if let Some(n_arg_group) = group {
-                let n_args_string = tokens_as_string(n_arg_group.into_iter())?;
-                str::parse::<usize>(&n_args_string).map_err(|_| ParseError::ExpectedNumber)?
+        // As in TeX, macros in the body are expanded when the macro is used, not when it is defined
+        let mut expansion = token_iter.capture_unexpanded_group()?;
+        if let DefinitionKind::DeclareMathOperator = kind {
+            let mut operator = vec![TexToken::ControlSequence("operatorname")];
+            if starred {
+                operator.push(TexToken::Char('*'));
             }
-            else 
-            { 0 }
-        ;
-
-        let group = token_iter.capture_group()?;
+            operator.push(TexToken::BeginGroup);
+            operator.append(&mut expansion);
+            operator.push(TexToken::EndGroup);
+            expansion = operator;
+        }
 
-        let mut expansion = Vec::with_capacity(group.len());
         // check if any error occurred
-        for token in group {
-            let command_token = CommandToken::try_from(token)?;
-            if let CommandToken::ArgSlot(i) = &command_token {
-                if *i >= n_args {
+        for token in expansion.iter() {
+            if let TexToken::Argument(i) = token {
+                if *i == 0 {
+                    return Err(ParseError::IllegalParameterNumber)
+                }
+                if *i > n_args {
                     return Err(ParseError::MoreArgsThanSpecified)
                 }
             }
-            expansion.push(command_token);
         }
 
-
-        let custom_command = CustomCommand {
+        Ok(Self {
             n_args,
-            name: name.to_string(),
+            name,
             expansion,
-        };
-        
-        Ok(custom_command)
+            default_arg,
+        })
     }
-
 }
 
+/// Maximum number of macro expansions in a formula, so that recursive macros (e.g. `\def\x{\x}`) fail instead of looping forever.
+const MAX_EXPANSIONS : usize = 1000;
+
 /// Wraps a token iterator, expanding every command token that correspond to a macro.
 pub struct ExpandedTokenIter<'a, I : Iterator<Item = TexToken<'a>>> {
     command_collection : & 'a CommandCollection,
     token_iter : I,
     /// token obtained from macro expansion
     expanded_token : Vec<TexToken<'a>>, 
+    /// macros defined in the input itself, one scope for each group currently open
+    local_commands : Vec<Vec<LocalCommand<'a>>>,
+    /// number of macros expanded so far, bounded by [`MAX_EXPANSIONS`]
+    n_expansions : usize,
 }
 
 impl<'a, I : Iterator<Item = TexToken<'a>>> Iterator for ExpandedTokenIter<'a, I> {
@@ -261,35 +418,53 @@ impl<'a, I : Iterator<Item = TexToken<'a>>> ExpandedTokenIter<'a, I> {
 
     /// Get next token from the iterator
     pub fn next_token(&mut self) -> ParseResult<Option<TexToken<'a>>> {
-        if let Some(token) = self.produce_next_token() {
-            if let TexToken::ControlSequence(command) = token {
-                if let Some(command) = self.command_collection.get(command) {
+        let token = self.next_expanded_token()?;
+        // Macros defined within a group are forgotten at the end of this group
+        match token {
+            Some(TexToken::BeginGroup) => self.local_commands.push(Vec::new()),
+            Some(TexToken::EndGroup) if self.local_commands.len() > 1 => { self.local_commands.pop(); },
+            _ => (),
+        }
+        Ok(token)
+    }
+
+    fn next_expanded_token(&mut self) -> ParseResult<Option<TexToken<'a>>> {
+        while let Some(token) = self.produce_next_token() {
+            let TexToken::ControlSequence(command) = token else {
+                return Ok(Some(token));
+            };
+            if let Some(kind) = DefinitionKind::from_name(command) {
+                self.define_local_command(kind)?;
+                continue;
+            }
+
+            let expanded_tokens = 
+                if let Some(command) = self.local_command(command).cloned() {
+                    let tokens = self.gather_args(command.n_args, command.default_arg.clone())?;
+                    command.expand(&tokens)
+                }
+                else if let Some(command) = self.command_collection.get(command) {
                     let tokens: Vec<Vec<TexToken<'a>>> = self.gather_args_of_command(command)?;
                     let token_slice : & [Vec<TexToken<'a>>] = tokens.as_slice();
-                    // TODO: something not to have to do reversals
-                    let mut expanded_tokens : Vec<TexToken<'a>> = command.expand_iter(token_slice).collect();
-                    self.expanded_token.reserve(expanded_tokens.len());
-                    while let Some(token) = expanded_tokens.pop() {
-                        self.expanded_token.push(token)
-                    }
-                    self.next_token()
+                    command.expand_iter(token_slice).collect()
                 }
                 else {
-                    Ok(Some(token))
+                    return Ok(Some(token));
                 }
+            ;
+
+            self.n_expansions += 1;
+            if self.n_expansions > MAX_EXPANSIONS {
+                return Err(ParseError::TooManyMacroExpansions);
             }
-            else {
-                Ok(Some(token))
-            }
-        }
-        else {
-            Ok(None)            
+            self.push_expansion(expanded_tokens);
         }
+        Ok(None)
     }
 
     /// Obtain next token from the iterator and places it back on the expansion stack, so that a next call to either [`ExpandedTokenIter::next_token`] or [`ExpandedTokenIter::peek_token`] will return the same token.
     pub fn peek_token(&mut self) -> ParseResult<Option<TexToken<'a>>> {
-        let token = self.next_token()?;
+        let token = self.next_expanded_token()?;
         if let Some(token) = token.clone() {
             self.expanded_token.push(token);
         }
@@ -298,9 +473,97 @@ impl<'a, I : Iterator<Item = TexToken<'a>>> ExpandedTokenIter<'a, I> {
 
     /// From a regular token iterator, creates one that expands macros.
     pub fn new<'command : 'a>(command_collection: & 'command CommandCollection, token_iter: I) -> Self {
-        Self { command_collection, token_iter, expanded_token: Vec::new() }
+        Self { command_collection, token_iter, expanded_token: Vec::new(), local_commands: vec![Vec::new()], n_expansions: 0 }
     }
 
+    fn push_expansion(&mut self, mut expanded_tokens : Vec<TexToken<'a>>) {
+        // TODO: something not to have to do reversals
+        self.expanded_token.reserve(expanded_tokens.len());
+        while let Some(token) = expanded_tokens.pop() {
+            self.expanded_token.push(token)
+        }
+    }
+
+    /// Finds the innermost definition of a macro defined in the input.
+    fn local_command(&self, name : &str) -> Option<&LocalCommand<'a>> {
+        self.local_commands
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter())
+            .find(|command| command.name == name)
+    }
+
+    fn define_local_command(&mut self, kind : DefinitionKind) -> ParseResult<()> {
+        let command = LocalCommand::parse_definition_from_iter(kind, self)?;
+        let is_macro = self.local_command(command.name).is_some() || self.command_collection.get(command.name).is_some();
+        kind.check_definition(command.name, is_macro)?;
+
+        let scope = self.local_commands
+            .last_mut()
+            .expect("the outermost scope is never removed");
+        scope.retain(|other| other.name != command.name);
+        scope.push(command);
+        Ok(())
+    }
+
+    /// Returns the name of the macro being defined in `\newcommand{\foo}..` or `\def\foo..`, without expanding it.
+    fn capture_macro_name(&mut self) -> ParseResult<&'a str> {
+        match self.next_unexpanded_non_whitespace_token() {
+            Some(TexToken::ControlSequence(name)) => Ok(name),
+            Some(TexToken::BeginGroup) => {
+                let name = match self.next_unexpanded_non_whitespace_token() {
+                    Some(TexToken::ControlSequence(name)) => name,
+                    _ => return Err(ParseError::ExpectedMacroName),
+                };
+                match self.next_unexpanded_non_whitespace_token() {
+                    Some(TexToken::EndGroup) => Ok(name),
+                    _ => Err(ParseError::ExpectedMacroName),
+                }
+            },
+            _ => Err(ParseError::ExpectedMacroName),
+        }
+    }
+
+    /// Returns the tokens of the next group (or the next token if it is not a group), without expanding macros.
+    fn capture_unexpanded_group(&mut self) -> ParseResult<Vec<TexToken<'a>>> {
+        let token = self.next_unexpanded_non_whitespace_token()
+            .ok_or(ParseError::ExpectedToken)?;
+        if token != TexToken::BeginGroup {
+            return Ok(vec![token]);
+        }
+
+        let mut group = Vec::new();
+        let mut n_open_paren : u32 = 1;
+        loop {
+            let token = self.produce_next_token()
+                .ok_or(ParseError::UnmatchedBrackets)?;
+            match token {
+                TexToken::BeginGroup => n_open_paren += 1,
+                TexToken::EndGroup   => n_open_paren -= 1,
+                _ => (),
+            }
+            if n_open_paren == 0 {
+                return Ok(group);
+            }
+            group.push(token);
+        }
+    }
+
+    fn next_unexpanded_non_whitespace_token(&mut self) -> Option<TexToken<'a>> {
+        let mut token = self.produce_next_token();
+        while let Some(TexToken::WhiteSpace) = token {
+            token = self.produce_next_token();
+        }
+        token
+    }
+
+    pub(super) fn peek_unexpanded_non_whitespace_token(&mut self) -> Option<TexToken<'a>> {
+        let token = self.next_unexpanded_non_whitespace_token();
+        if let Some(token) = token.clone() {
+            self.expanded_token.push(token);
+        }
+        token
+    }
 
     fn produce_next_token(&mut self) -> Option<TexToken<'a>> {
         Option::or_else(
@@ -309,10 +572,18 @@ impl<'a, I : Iterator<Item = TexToken<'a>>> ExpandedTokenIter<'a, I> {
         )
     }
 
-    fn gather_args_of_command(&mut self, command : &CustomCommand) -> ParseResult<Vec<Vec<TexToken<'a>>>> {
-        let n_args = command.n_args();
+    fn gather_args_of_command(&mut self, command : &'a CustomCommand) -> ParseResult<Vec<Vec<TexToken<'a>>>> {
+        self.gather_args(command.n_args(), command.default_arg())
+    }
+
+    /// Captures the arguments of a macro ; if `default_arg` is given, the first argument is optional and enclosed in brackets.
+    fn gather_args(&mut self, n_args : usize, default_arg : Option<Vec<TexToken<'a>>>) -> ParseResult<Vec<Vec<TexToken<'a>>>> {
         let mut args : Vec<Vec<TexToken>> = Vec::with_capacity(n_args);
-        for i in 0 .. n_args {
+        if let Some(default_arg) = default_arg {
+            let arg = self.capture_optional_group()?.unwrap_or(default_arg);
+            args.push(arg);
+        }
+        for i in args.len() .. n_args {
             let arg = self
                 .capture_group()
                 .map_err(|e| match e {
@@ -352,6 +623,13 @@ impl<'a, I : Iterator<Item = TexToken<'a>>> ExpandedTokenIter<'a, I> {
             },
             TexToken::ControlSequence(command_name) => {
                 arg.push(token);
+                if PrimitiveControlSequence::takes_optional_arg(command_name) {
+                    if let Some(optional_arg) = self.capture_optional_group()? {
+                        arg.push(TexToken::Char('['));
+                        arg.extend(optional_arg);
+                        arg.push(TexToken::Char(']'));
+                    }
+                }
                 let n_args = PrimitiveControlSequence::n_args(command_name).unwrap_or(0);
                 for _ in 0 .. n_args {
                     arg.push(TexToken::BeginGroup);
@@ -439,6 +717,7 @@ mod tests {
                 n_args: 2,
                 name: name.to_string(),
                 expansion,
+                default_arg: None,
             }          
         }
 
@@ -454,6 +733,7 @@ mod tests {
                 n_args: 2,
                 name: name.to_string(),
                 expansion,
+                default_arg: None,
             }          
         }
 
@@ -467,6 +747,7 @@ mod tests {
                 n_args: 1,
                 name: name.to_string(),
                 expansion,
+                default_arg: None,
             }          
         }
     }
@@ -749,6 +1030,16 @@ mod tests {
             CommandToken::ArgSlot(1)
         ]);
 
+        let underlying_string = r"\newcommand{\abc}[2][x]{#1+#2}";
+        let token_iter = TokenIterator::new(underlying_string);
+        let mut expanded_token_iter = ExpandedTokenIter::new(&collection, token_iter);
+
+        let command = CustomCommand::parse_macro_definition_from_iter(&mut expanded_token_iter).unwrap();
+        assert_eq!(command.n_args, 2);
+        assert_eq!(command.default_arg, Some(vec![
+            CommandToken::NormalToken(TexToken::Char('x')),
+        ]));
+
         let underlying_string = r"\newcommand{\abc}[2]{#1+#3}";
         let token_iter = TokenIterator::new(underlying_string);
         let mut expanded_token_iter = ExpandedTokenIter::new(&collection, token_iter);
@@ -771,5 +1062,52 @@ mod tests {
         "#;
 
         CommandCollection::parse(file).unwrap();
+
+        let file = r#"
+        \newcommand{\abc}{a}
+        \renewcommand*{\abc}{b}
+        \def\pair#1#2{(#1, #2)}
+        "#;
+
+        let collection = CommandCollection::parse(file).unwrap();
+        assert_eq!(collection.0.len(), 2);
+        assert_eq!(collection.get("abc").unwrap().expansion, vec![
+            CommandToken::NormalToken(TexToken::Char('b')),
+        ]);
+        assert_eq!(collection.get("pair").unwrap().n_args(), 2);
+
+        CommandCollection::parse(r"\newcommand{\abc}{a} b").unwrap_err();
+        assert_eq!(
+            CommandCollection::parse(r"\newcommand{\abc}{a} \newcommand{\abc}{b}"),
+            Err(ParseError::MacroAlreadyDefined(Box::from("abc"))),
+        );
+        assert_eq!(
+            CommandCollection::parse(r"\newcommand{\frac}{a}"),
+            Err(ParseError::MacroAlreadyDefined(Box::from("frac"))),
+        );
+        assert_eq!(
+            CommandCollection::parse(r"\renewcommand{\abc}{a}"),
+            Err(ParseError::MacroNotDefined(Box::from("abc"))),
+        );
+
+        let collection = CommandCollection::parse(r"\DeclareMathOperator*{\argmax}{argmax}").unwrap();
+        assert_eq!(collection.get("argmax").unwrap().expansion[.. 3], [
+            CommandToken::OwnedCommand("operatorname".to_string()),
+            CommandToken::NormalToken(TexToken::Char('*')),
+            CommandToken::NormalToken(TexToken::BeginGroup),
+        ]);
+    }
+
+    #[test]
+    fn check_optional_argument() {
+        let collection = CommandCollection::parse(r"\newcommand{\abc}[2][x]{#1#2}").unwrap();
+        assert!(collection.get("abc").unwrap().has_optional_arg());
+
+        let expand = |input| -> Vec<_> {
+            ExpandedTokenIter::new(&collection, TokenIterator::new(input)).collect()
+        };
+        assert_eq!(expand(r"\abc y"),        vec![TexToken::Char('x'), TexToken::Char('y')]);
+        assert_eq!(expand(r"\abc[z]y"),      vec![TexToken::Char('z'), TexToken::Char('y')]);
+        assert_eq!(expand(r"\abc [] {y}"),   vec![TexToken::Char('y')]);
     }
 }
\ No newline at end of file
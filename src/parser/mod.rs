@@ -8,20 +8,26 @@ pub mod symbols;
 pub mod macros;
 pub mod error;
 pub mod environments;
+mod chemistry;
+mod units;
+mod text;
 mod textoken;
 mod control_sequence;
 
 use unicode_math::TexSymbolType;
 
-use crate::dimensions::AnyUnit;
+use crate::dimensions::{AnyUnit, Unit};
+use crate::layout::constants;
 use crate::error::ParseResult;
-use crate::font::style_symbol;
+use crate::font::{has_bold_variant, style_symbol};
 use crate::font::Style;
 use crate::parser::control_sequence::parse_color;
 use crate::parser::nodes::Accent;
+use crate::parser::nodes::ArrayColumnAlign;
+use crate::parser::nodes::BarThickness;
 use crate::parser::nodes::Delimited;
 use crate::parser::nodes::GenFraction;
-use crate::parser::nodes::PlainText;
+use crate::parser::nodes::MathStyle;
 use crate::parser::textoken::TexToken;
 use crate::parser::control_sequence::PrimitiveControlSequence;
 
@@ -33,6 +39,7 @@ use self::macros::ExpandedTokenIter;
 pub use self::nodes::ParseNode;
 pub use self::nodes::is_symbol;
 use self::nodes::Scripts;
+use self::color::{ColorPalette, RGBA};
 use self::symbols::Symbol;
 use self::textoken::NumberOfPrimes;
 use self::textoken::TokenIterator;
@@ -76,6 +83,9 @@ struct List {
     group : GroupKind
 }
 
+/// Labels placed above and below a construction, e.g. the `^{..}` and `_{..}` following `\underbrace{..}`
+type Labels = (Option<Vec<ParseNode>>, Option<Vec<ParseNode>>);
+
 
 /// Contains the internal state of the TeX parser, what's left to parse, and has methods to parse various TeX construct.  
 /// Holds a reference to `CommandCollection`, which holds the definition of custom TeX macros defined by the user.
@@ -83,6 +93,10 @@ struct List {
 pub struct Parser<'a, I : Iterator<Item = TexToken<'a>>> {
     token_iter : ExpandedTokenIter<'a, I>,
     current_style : Style,
+    /// Whether symbols without a bold counterpart are emboldened by overstriking them, as in `\boldsymbol{..}`
+    poor_mans_bold : bool,
+    /// Named colors, as defined by `\definecolor{..}{..}{..}`
+    color_palette : ColorPalette,
 }
 
 impl<'a> Parser<'a, TokenIterator<'a>> {
@@ -91,7 +105,9 @@ impl<'a> Parser<'a, TokenIterator<'a>> {
     pub fn new<'command : 'a, 'input : 'a>(command_collection: & 'command CommandCollection, input: & 'input str) -> Self { 
         Self { 
             token_iter : ExpandedTokenIter::new(command_collection, TokenIterator::new(input)),
-            current_style : Style::default()
+            current_style : Style::default(),
+            poor_mans_bold : false,
+            color_palette : ColorPalette::new(),
         } 
     }
 }
@@ -105,9 +121,17 @@ impl<'a, I : Iterator<Item = TexToken<'a>>> Parser<'a, I> {
         Self { 
             token_iter : ExpandedTokenIter::new(command_collection, input),
             current_style: Style::default(),
+            poor_mans_bold: false,
+            color_palette: ColorPalette::new(),
         } 
     }
 
+    /// Makes the named colors of `color_palette` available to commands like `\color{..}`
+    pub fn with_color_palette(mut self, color_palette : &ColorPalette) -> Self {
+        self.color_palette = color_palette.clone();
+        self
+    }
+
 
     /// Parses the input into an array of [`ParseNode`].
     pub fn parse(&mut self) -> ParseResult<Vec<ParseNode>> {
@@ -123,6 +147,23 @@ impl<'a, I : Iterator<Item = TexToken<'a>>> Parser<'a, I> {
 
 
     fn parse_until_end_of_group(&mut self) -> ParseResult<List> {
+        let mut infix_fraction = None;
+        let List { nodes, group } = self.parse_group_items(&mut infix_fraction)?;
+
+        // After an infix command like `\over`, the rest of the group is the denominator
+        let nodes = match infix_fraction {
+            Some(mut fraction) => {
+                fraction.denominator = nodes;
+                vec![ParseNode::GenFraction(fraction)]
+            },
+            None => nodes,
+        };
+        Ok(List { nodes, group })
+    }
+
+    /// Parses the items of a group ; if an infix command like `\over` is met, the items collected so far are moved 
+    /// to the numerator of `infix_fraction` and the items returned are those which follow.
+    fn parse_group_items(&mut self, infix_fraction: &mut Option<GenFraction>) -> ParseResult<List> {
         let mut results = Vec::new();
 
         while let Some(token) = self.token_iter.next_token()? {
@@ -150,6 +191,8 @@ impl<'a, I : Iterator<Item = TexToken<'a>>> Parser<'a, I> {
                                 base: Some(Box::new(node)), 
                                 superscript: None,
                                 subscript: None, 
+                                presuperscript: None,
+                                presubscript: None,
                             };
                             *scripts.get_script(is_superscript) = Some(group);
                             ParseNode::Scripts(scripts)
@@ -159,6 +202,8 @@ impl<'a, I : Iterator<Item = TexToken<'a>>> Parser<'a, I> {
                                 base: None, 
                                 superscript: None,
                                 subscript: None, 
+                                presuperscript: None,
+                                presubscript: None,
                             };
                             *scripts.get_script(is_superscript) = Some(group);
                             ParseNode::Scripts(scripts)
@@ -180,10 +225,11 @@ impl<'a, I : Iterator<Item = TexToken<'a>>> Parser<'a, I> {
                 },
                 TexToken::WhiteSpace => { },
                 TexToken::BeginGroup => {
-                    // Font changes made within a group should not affect what happens outside of it
-                    let old_style = self.current_style;
+                    // Font changes and color definitions made within a group should not affect what happens outside of it
+                    let (old_style, old_palette) = (self.current_style, self.color_palette.clone());
                     let List { nodes, group } = self.parse_until_end_of_group()?;
                     self.current_style = old_style;
+                    self.color_palette = old_palette;
                     if group != GroupKind::BraceGroup {
                         return Err(ParseError::UnexpectedEndGroup{expected: Box::from([GroupKind::BraceGroup]), got: group});
                     }
@@ -197,8 +243,8 @@ impl<'a, I : Iterator<Item = TexToken<'a>>> Parser<'a, I> {
                     return Ok(List { nodes: results, group: GroupKind::Align });
                 },
                 TexToken::Char(codepoint) => {
-                    let symbol = self.char_to_symbol(codepoint)?;
-                    results.push(ParseNode::Symbol(symbol));
+                    let atom_type = codepoint_atom_type(codepoint).ok_or(ParseError::UnrecognizedSymbol(codepoint))?;
+                    results.push(self.styled_symbol_node(Symbol { codepoint, atom_type }));
                 },
                 TexToken::ControlSequence("\\") => {
                     return Ok(List { nodes: results, group: GroupKind::NewLine });
@@ -212,8 +258,9 @@ impl<'a, I : Iterator<Item = TexToken<'a>>> Parser<'a, I> {
                     use PrimitiveControlSequence::*;
                     match command {
                         Radical(character) => {
+                            let degree = self.parse_optional_argument_as_nodes()?;
                             let inner = self.parse_control_seq_argument_as_nodes(control_sequence_name)?;
-                            results.push(ParseNode::Radical(nodes::Radical { inner, character, }));
+                            results.push(ParseNode::Radical(nodes::Radical { inner, character, degree, }));
                         },
                         Rule => {
                             let width_tokens = self.token_iter.capture_group().map_err(|e| match e {
@@ -235,17 +282,31 @@ impl<'a, I : Iterator<Item = TexToken<'a>>> Parser<'a, I> {
                             }))
                         },
                         Color => {
-                            let color_name_group = self.token_iter.capture_group().map_err(|e| match e {
-                                ParseError::ExpectedToken => ParseError::MissingArgForCommand(Box::from(control_sequence_name)),
-                                _ => e,
-                            })?;
-                            let color = parse_color(color_name_group.into_iter())?;
+                            // Like a font change, the color applies to the rest of the current group
+                            let color = self.parse_color_argument(control_sequence_name)?;
+                            // An infix command like `\over` met in the rest of the group still splits the whole group
+                            let had_infix_fraction = infix_fraction.is_some();
+                            let List { nodes: inner, group } = self.parse_group_items(infix_fraction)?;
+                            if let (false, Some(fraction)) = (had_infix_fraction, infix_fraction.as_mut()) {
+                                let numerator = std::mem::take(&mut fraction.numerator);
+                                results.push(ParseNode::Color(nodes::Color { color, inner: numerator }));
+                                fraction.numerator = std::mem::take(&mut results);
+                            }
+                            results.push(ParseNode::Color(nodes::Color {
+                                color,
+                                inner,
+                            }));
+                            return Ok(List { nodes: results, group });
+                        },
+                        TextColor => {
+                            let color = self.parse_color_argument(control_sequence_name)?;
                             let inner = self.parse_control_seq_argument_as_nodes(control_sequence_name)?;
                             results.push(ParseNode::Color(nodes::Color {
                                 color,
                                 inner,
                             }));
                         },
+                        DefineColor => self.parse_color_definition(control_sequence_name)?,
                         ColorLit(color) => {
                             let inner = self.parse_control_seq_argument_as_nodes(control_sequence_name)?;
                             results.push(ParseNode::Color(nodes::Color {
@@ -253,6 +314,25 @@ impl<'a, I : Iterator<Item = TexToken<'a>>> Parser<'a, I> {
                                 inner,
                             }));
                         },
+                        Framed { text_mode, border, fill } => {
+                            let border_color = if border && fill { Some(self.parse_color_argument(control_sequence_name)?) } else { None };
+                            let fill_color   = if fill { Some(self.parse_color_argument(control_sequence_name)?) } else { None };
+                            let inner = if text_mode {
+                                let text_group = self.capture_control_seq_group(control_sequence_name)?;
+                                self.parse_text(text_group, text::TEXT_STYLE)?
+                            }
+                            else {
+                                self.parse_control_seq_argument_as_nodes(control_sequence_name)?
+                            };
+                            let border_thickness = if border { constants::FBOX_RULE } else { Unit::ZERO };
+                            results.push(ParseNode::Framed(nodes::Framed {
+                                inner,
+                                padding: constants::FBOX_SEP.into(),
+                                border_thickness: border_thickness.into(),
+                                border_color,
+                                fill_color,
+                            }));
+                        },
                         StyleChange { family, weight, takes_arg } => {
                             let old_style = self.current_style;
                             if let Some(family) = family {
@@ -276,8 +356,78 @@ impl<'a, I : Iterator<Item = TexToken<'a>>> Parser<'a, I> {
                                 numerator, denominator,
                                 left_delimiter, right_delimiter,
                                 bar_thickness, style,
+                                numerator_align: ArrayColumnAlign::Centered,
+                            }));
+                        },
+                        GenFrac => {
+                            let left_delimiter  = self.parse_fraction_delimiter(control_sequence_name)?;
+                            let right_delimiter = self.parse_fraction_delimiter(control_sequence_name)?;
+
+                            let thickness = tokens_as_string(self.capture_control_seq_group(control_sequence_name)?.into_iter())?;
+                            let bar_thickness = match thickness.trim() {
+                                "" => BarThickness::Default,
+                                thickness => BarThickness::Unit(parse_dimension(thickness)?),
+                            };
+
+                            let style = match tokens_as_string(self.capture_control_seq_group(control_sequence_name)?.into_iter())?.trim() {
+                                ""  => MathStyle::NoChange,
+                                "0" => MathStyle::Display,
+                                "1" => MathStyle::Text,
+                                "2" => MathStyle::Script,
+                                "3" => MathStyle::ScriptScript,
+                                style => return Err(ParseError::UnrecognizedFractionStyle(Box::from(style))),
+                            };
+
+                            let numerator   = self.parse_control_seq_argument_as_nodes(control_sequence_name)?;
+                            let denominator = self.parse_control_seq_argument_as_nodes(control_sequence_name)?;
+
+                            results.push(ParseNode::GenFraction(GenFraction {
+                                numerator, denominator,
+                                left_delimiter, right_delimiter,
+                                bar_thickness, style,
+                                numerator_align: ArrayColumnAlign::Centered,
+                            }));
+                        },
+                        ContinuedFraction => {
+                            let numerator_align = match self.token_iter.capture_optional_group()? {
+                                None => ArrayColumnAlign::Centered,
+                                Some(option) => match tokens_as_string(option.into_iter())?.trim() {
+                                    "l" => ArrayColumnAlign::Left,
+                                    "c" => ArrayColumnAlign::Centered,
+                                    "r" => ArrayColumnAlign::Right,
+                                    option => return Err(ParseError::UnrecognizedFractionAlignment(Box::from(option))),
+                                },
+                            };
+
+                            // As in amsmath, both parts of a continued fraction are set in display style, so that nested fractions don't shrink,
+                            // while the fraction itself is set in the current style
+                            let mut numerator   = vec![ParseNode::Style(crate::layout::Style::Display)];
+                            numerator.extend(self.parse_control_seq_argument_as_nodes(control_sequence_name)?);
+                            let mut denominator = vec![ParseNode::Style(crate::layout::Style::Display)];
+                            denominator.extend(self.parse_control_seq_argument_as_nodes(control_sequence_name)?);
+
+                            results.push(ParseNode::GenFraction(GenFraction {
+                                numerator, denominator,
+                                left_delimiter: None, right_delimiter: None,
+                                bar_thickness: BarThickness::Default, 
+                                style: MathStyle::NoChange,
+                                numerator_align,
                             }));
                         },
+                        InfixFraction(left_delimiter, right_delimiter, bar_thickness) => {
+                            if infix_fraction.is_some() {
+                                return Err(ParseError::AmbiguousInfixFraction);
+                            }
+                            *infix_fraction = Some(GenFraction {
+                                numerator: std::mem::take(&mut results),
+                                // filled at the end of the group
+                                denominator: Vec::new(),
+                                left_delimiter, right_delimiter,
+                                bar_thickness, 
+                                style: MathStyle::NoChange,
+                                numerator_align: ArrayColumnAlign::Centered,
+                            });
+                        },
                         ExtendedDelimiter(delimiter_size, atom_type) => {
                             let mut delimiter = self.parse_next_token_as_delimiter()?;
                             match delimiter.atom_type {
@@ -297,6 +447,20 @@ impl<'a, I : Iterator<Item = TexToken<'a>>> Parser<'a, I> {
                         Kerning(space) => {
                             results.push(ParseNode::Kerning(space))
                         },
+                        Skip { math_units, star_form } => {
+                            if star_form {
+                                if let Some(TexToken::Char('*')) = self.token_iter.peek_token()? {
+                                    self.token_iter.next_token()?;
+                                }
+                            }
+                            let space = self.parse_dimension_argument(control_sequence_name)?;
+                            match (math_units, matches!(space, AnyUnit::Mu(_))) {
+                                (true, false) => return Err(ParseError::ExpectedMathUnit(Box::from(control_sequence_name))),
+                                (false, true) => return Err(ParseError::UnexpectedMathUnit(Box::from(control_sequence_name))),
+                                _ => (),
+                            }
+                            results.push(ParseNode::Kerning(space))
+                        },
                         StyleCommand(style) => {
                             results.push(ParseNode::Style(style));
                         },
@@ -312,18 +476,208 @@ impl<'a, I : Iterator<Item = TexToken<'a>>> Parser<'a, I> {
                                 inner,
                             }));
                         },
+                        ResizedBox { visible, width, keep_height, keep_depth } => {
+                            let inner = self.parse_control_seq_argument_as_nodes(control_sequence_name)?;
+                            results.push(ParseNode::ResizedBox(nodes::ResizedBox {
+                                inner,
+                                visible,
+                                width,
+                                keep_height,
+                                keep_depth,
+                            }));
+                        },
+                        Smash => {
+                            let (keep_height, keep_depth) = match self.token_iter.capture_optional_group()? {
+                                None => (false, false),
+                                Some(option) => match tokens_as_string(option.into_iter())?.trim() {
+                                    "t" => (false, true),
+                                    "b" => (true, false),
+                                    option => return Err(ParseError::UnrecognizedSmashOption(Box::from(option))),
+                                },
+                            };
+                            let inner = self.parse_control_seq_argument_as_nodes(control_sequence_name)?;
+                            results.push(ParseNode::ResizedBox(nodes::ResizedBox {
+                                inner,
+                                visible: true,
+                                width: nodes::BoxWidth::Natural,
+                                keep_height,
+                                keep_depth,
+                            }));
+                        },
+                        Cancel(kind) => {
+                            let inner = self.parse_control_seq_argument_as_nodes(control_sequence_name)?;
+                            results.push(ParseNode::Cancel(nodes::Cancel {
+                                inner,
+                                kind,
+                                to: None,
+                            }));
+                        },
+                        CancelTo => {
+                            let to    = self.parse_control_seq_argument_as_nodes(control_sequence_name)?;
+                            let inner = self.parse_control_seq_argument_as_nodes(control_sequence_name)?;
+                            results.push(ParseNode::Cancel(nodes::Cancel {
+                                inner,
+                                kind: nodes::CancelKind::Forward,
+                                to: Some(to),
+                            }));
+                        },
                         TextOperator(op_name, limits_placement) => {
                             results.push(make_operator(op_name, limits_placement));
                         },
+                        BinaryMod => {
+                            // As in amsmath, `\bmod` is surrounded by 5mu in all styles: 
+                            // outside of script styles, the medium space around binary operators is reduced accordingly
+                            let kern = nodes::StyledKerning { 
+                                display: AnyUnit::Mu(1.), 
+                                text:    AnyUnit::Mu(1.), 
+                                script:  AnyUnit::Mu(5.),
+                            };
+                            let mut operator = make_operator("mod", false);
+                            operator.set_atom_type(TexSymbolType::Binary);
+                            results.push(ParseNode::StyledKerning(kern));
+                            results.push(operator);
+                            results.push(ParseNode::StyledKerning(kern));
+                        },
+                        Mod { parenthesized, word } => {
+                            let inner = self.parse_control_seq_argument_as_nodes(control_sequence_name)?;
+
+                            // As in amsmath, these are preceded by a quad in display style and by a smaller space otherwise
+                            let inline_space = AnyUnit::Mu(if parenthesized { 8. } else { 12. });
+                            results.push(ParseNode::StyledKerning(nodes::StyledKerning {
+                                display: SpaceKind::QuadSpace.size(),
+                                text:    inline_space,
+                                script:  inline_space,
+                            }));
+                            if parenthesized {
+                                results.push(ParseNode::Symbol(self.char_to_symbol('(')?));
+                            }
+                            if word {
+                                // "mod" is set like an operator name, but without the spacing of an operator
+                                let mut operator = make_operator("mod", false);
+                                operator.set_atom_type(TexSymbolType::Alpha);
+                                results.push(operator);
+                                results.push(ParseNode::Kerning(SpaceKind::ThinSpace.size()));
+                                results.push(ParseNode::Kerning(SpaceKind::ThinSpace.size()));
+                            }
+                            results.extend(inner);
+                            if parenthesized {
+                                results.push(ParseNode::Symbol(self.char_to_symbol(')')?));
+                            }
+                        },
+                        BoldSymbol => {
+                            let (old_style, old_poor_mans_bold) = (self.current_style, self.poor_mans_bold);
+                            self.current_style = self.current_style.with_bold_symbol();
+                            self.poor_mans_bold = true;
+                            let nodes = self.parse_control_seq_argument_as_nodes(control_sequence_name);
+                            self.current_style = old_style;
+                            self.poor_mans_bold = old_poor_mans_bold;
+                            let nodes = nodes?;
+                            // A single atom keeps its class, so that e.g. `a \boldsymbol{=} b` is spaced as a relation
+                            match nodes.as_slice() {
+                                [node] => results.push(ParseNode::AtomChange(nodes::AtomChange {
+                                    at: node.atom_type(),
+                                    inner: nodes,
+                                })),
+                                _ => results.push(ParseNode::Group(nodes)),
+                            }
+                        },
+                        PoorMansBold => {
+                            let inner = self.parse_control_seq_argument_as_nodes(control_sequence_name)?;
+                            results.push(ParseNode::PoorMansBold(nodes::PoorMansBold { inner }));
+                        },
+                        MathChoice => {
+                            let display      = self.parse_control_seq_argument_as_nodes(control_sequence_name)?;
+                            let text         = self.parse_control_seq_argument_as_nodes(control_sequence_name)?;
+                            let script       = self.parse_control_seq_argument_as_nodes(control_sequence_name)?;
+                            let scriptscript = self.parse_control_seq_argument_as_nodes(control_sequence_name)?;
+                            results.push(ParseNode::MathChoice(nodes::MathChoice { display, text, script, scriptscript }));
+                        },
                         OperatorName => {
+                            // `\operatorname*{..}` places its scripts as limits
+                            let limits_placement = self.token_iter.peek_token()? == Some(TexToken::Char('*'));
+                            if limits_placement {
+                                self.token_iter.next_token()?;
+                            }
+
                             // Capture operator name
                             let text_group = self.token_iter.capture_group().map_err(|e| match e {
                                 ParseError::ExpectedToken => ParseError::MissingArgForCommand(Box::from(control_sequence_name)),
                                 _ => e,
                             })?;
                             let op_name = tokens_as_string(text_group.into_iter())?;
-                            results.push(make_operator(&op_name, false));
+                            results.push(make_operator(&op_name, limits_placement));
                         }
+                        OverUnderSet { under, atom_type } => {
+                            let label = self.parse_control_seq_argument_as_nodes(control_sequence_name)?;
+                            let base  = self.parse_control_seq_argument_as_nodes(control_sequence_name)?;
+                            // As in amsmath, the construction keeps the spacing of a relation or binary operator
+                            let atom_type = atom_type.unwrap_or_else(|| match base.as_slice() {
+                                [node] if matches!(node.atom_type(), TexSymbolType::Relation | TexSymbolType::Binary) => node.atom_type(),
+                                _ => TexSymbolType::Alpha,
+                            });
+                            let (over, under) = if under { (None, Some(label)) } else { (Some(label), None) };
+                            results.push(ParseNode::OverUnder(nodes::OverUnder {
+                                base,
+                                over,
+                                under,
+                                stretchy: false,
+                                atom_type,
+                            }));
+                        },
+                        ExtensibleArrow(arrow) => {
+                            let under = self.parse_optional_argument_as_nodes()?;
+                            let over  = self.parse_control_seq_argument_as_nodes(control_sequence_name)?;
+                            results.push(ParseNode::ExtensibleArrow(nodes::ExtensibleArrow { arrow, over, under }));
+                        },
+                        Chemistry => {
+                            let group = self.capture_control_seq_group(control_sequence_name)?;
+                            results.push(ParseNode::Group(chemistry::parse_chemistry(group.into_iter())?));
+                        },
+                        SiNumber => {
+                            let number = tokens_as_string(self.capture_control_seq_group(control_sequence_name)?.into_iter())?;
+                            results.push(ParseNode::Group(units::parse_number(&number)?));
+                        },
+                        SiUnit => {
+                            let unit = self.capture_control_seq_group(control_sequence_name)?;
+                            results.push(ParseNode::Group(units::parse_unit(unit)?));
+                        },
+                        SiQuantity => {
+                            let number = tokens_as_string(self.capture_control_seq_group(control_sequence_name)?.into_iter())?;
+                            let unit   = self.capture_control_seq_group(control_sequence_name)?;
+                            results.push(ParseNode::Group(units::parse_quantity(&number, unit)?));
+                        },
+                        SideSet => {
+                            let (presuperscript, presubscript) = self.parse_sideset_scripts(control_sequence_name)?;
+                            let (superscript, subscript)       = self.parse_sideset_scripts(control_sequence_name)?;
+                            let mut base = as_single_node(self.parse_control_seq_argument_as_nodes(control_sequence_name)?);
+
+                            // The scripts given to `\sideset` always go on the sides of the operator ; 
+                            // limits given afterwards (e.g. `\sideset{}{'}\sum_{n}`) are placed as usual for the operator
+                            let atom_type = base.atom_type();
+                            base.set_atom_type(TexSymbolType::Operator(false));
+                            results.push(ParseNode::AtomChange(nodes::AtomChange {
+                                at: atom_type,
+                                inner: vec![ParseNode::Scripts(Scripts {
+                                    base: Some(Box::new(base)),
+                                    superscript,
+                                    subscript,
+                                    presuperscript,
+                                    presubscript,
+                                })],
+                            }));
+                        },
+                        PreScript => {
+                            let presuperscript = self.parse_control_seq_argument_as_nodes(control_sequence_name)?;
+                            let presubscript   = self.parse_control_seq_argument_as_nodes(control_sequence_name)?;
+                            let base = as_single_node(self.parse_control_seq_argument_as_nodes(control_sequence_name)?);
+                            results.push(ParseNode::Scripts(Scripts {
+                                base: Some(Box::new(base)),
+                                superscript: None,
+                                subscript: None,
+                                presuperscript: Some(presuperscript).filter(|script| !script.is_empty()),
+                                presubscript: Some(presubscript).filter(|script| !script.is_empty()),
+                            }));
+                        },
                         SubStack(atom_type) => {
                             let group = self.token_iter.capture_group().map_err(|e| match e {
                                 ParseError::ExpectedToken => ParseError::MissingArgForCommand(Box::from(control_sequence_name)),
@@ -332,6 +686,8 @@ impl<'a, I : Iterator<Item = TexToken<'a>>> Parser<'a, I> {
 
                             let mut forked_parser = Parser::from_iter(Self::EMPTY_COMMAND_COLLECTION, group.into_iter());
                             forked_parser.current_style = self.current_style;
+                            forked_parser.poor_mans_bold = self.poor_mans_bold;
+                            forked_parser.color_palette = self.color_palette.clone();
 
                             let mut lines = Vec::new();
 
@@ -370,28 +726,17 @@ impl<'a, I : Iterator<Item = TexToken<'a>>> Parser<'a, I> {
 
                         }
                         Text => {
-                            let text_group = self.token_iter.capture_group().map_err(|e| match e {
-                                ParseError::ExpectedToken => ParseError::MissingArgForCommand(Box::from(control_sequence_name)),
-                                _ => e,
-                            })?;
-                            let text = tokens_as_string(text_group.into_iter())?;
-                            results.push(ParseNode::PlainText(PlainText {
-                                text,
-                            }));
+                            let text_group = self.capture_control_seq_group(control_sequence_name)?;
+                            let style = text::text_font(control_sequence_name, text::TEXT_STYLE).unwrap_or(text::TEXT_STYLE);
+                            // Text with embedded math is kept as a single node, e.g. for scripts to apply to all of it
+                            results.push(as_single_node(self.parse_text(text_group, style)?));
                         },
                         Mbox => {
-                            let text_group = self.token_iter.capture_group().map_err(|e| match e {
-                                ParseError::ExpectedToken => ParseError::MissingArgForCommand(Box::from(control_sequence_name)),
-                                _ => e,
-                            })?;
-                            let text = tokens_as_string(text_group.into_iter())?;
+                            let text_group = self.capture_control_seq_group(control_sequence_name)?;
                             // We create a group so as to scope the style change only to the \mbox not to later nodes
                             // Maybe setting the style in this way is too crude
-                            let mut nodes = Vec::with_capacity(2);
-                            nodes.push(ParseNode::Style(crate::layout::Style::Text));
-                            nodes.push(ParseNode::PlainText(PlainText {
-                                text,
-                            })); 
+                            let mut nodes = vec![ParseNode::Style(crate::layout::Style::Text)];
+                            nodes.extend(self.parse_text(text_group, text::TEXT_STYLE)?);
                             results.push(ParseNode::Group(nodes));
                         }
                         BeginEnv => {
@@ -468,25 +813,40 @@ impl<'a, I : Iterator<Item = TexToken<'a>>> Parser<'a, I> {
                                 self.parse_required_argument_as_nodes()?;
                             }
                         }
-                        SymbolCommand(mut symbol) => {
+                        SymbolCommand(symbol) => {
                             match symbol.atom_type {
                                   TexSymbolType::Accent 
                                 | TexSymbolType::AccentWide   
                                 | TexSymbolType::Over   
                                 | TexSymbolType::Under  => {
                                     let nucleus = self.parse_required_argument_as_nodes()?;
-                                    results.push(ParseNode::Accent(Accent {
+                                    let accent = ParseNode::Accent(Accent {
                                         symbol,
                                         nucleus,
                                         // Only "accent" are not extended
                                         extend: symbol.atom_type != TexSymbolType::Accent,
                                         under:  symbol.atom_type == TexSymbolType::Under,
-                                    }));
-                                },
-                                _ => {
-                                    self.style_symbol_with_current_style(&mut symbol);
-                                    results.push(ParseNode::Symbol(symbol));
+                                    });
+
+                                    // Like in LaTeX, `\overbrace{..}^{..}` and `\underbrace{..}_{..}` take their scripts as labels
+                                    let (over, under) = match symbol.atom_type {
+                                        TexSymbolType::Over | TexSymbolType::Under => self.parse_labels()?,
+                                        _ => (None, None),
+                                    };
+                                    if over.is_some() || under.is_some() {
+                                        results.push(ParseNode::OverUnder(nodes::OverUnder {
+                                            base: vec![accent],
+                                            over,
+                                            under,
+                                            stretchy: true,
+                                            atom_type: TexSymbolType::Operator(true),
+                                        }));
+                                    }
+                                    else {
+                                        results.push(accent);
+                                    }
                                 },
+                                _ => results.push(self.styled_symbol_node(symbol)),
                             }
                         },
                     }
@@ -503,6 +863,18 @@ impl<'a, I : Iterator<Item = TexToken<'a>>> Parser<'a, I> {
         *codepoint = style_symbol(*codepoint, self.current_style);
     }
 
+    /// Applies the current style to a symbol ; in `\boldsymbol{..}`, symbols without a bold counterpart are emboldened by overstriking instead.
+    fn styled_symbol_node(&self, mut symbol: Symbol) -> ParseNode {
+        let overstrike = self.poor_mans_bold && !has_bold_variant(symbol.codepoint);
+        self.style_symbol_with_current_style(&mut symbol);
+        if overstrike {
+            ParseNode::PoorMansBold(nodes::PoorMansBold { inner: vec![ParseNode::Symbol(symbol)] })
+        }
+        else {
+            ParseNode::Symbol(symbol)
+        }
+    }
+
     fn char_to_symbol(&self, codepoint: char) -> Result<Symbol, ParseError> {
         let atom_type = codepoint_atom_type(codepoint).ok_or_else(|| ParseError::UnrecognizedSymbol(codepoint))?;
         let mut symbol = Symbol { codepoint, atom_type };
@@ -518,6 +890,88 @@ impl<'a, I : Iterator<Item = TexToken<'a>>> Parser<'a, I> {
             })
     }
 
+    /// Parses a color argument, possibly preceded by a color model in brackets (e.g. `[rgb]{0.1,0.2,0.3}`)
+    fn parse_color_argument(&mut self, control_seq_name : &str) -> ParseResult<RGBA> {
+        let model = match self.token_iter.capture_optional_group()? {
+            Some(model) => Some(tokens_as_string(model.into_iter())?),
+            None        => None,
+        };
+        let color_name_group = self.token_iter.capture_group().map_err(|e| match e {
+            ParseError::ExpectedToken => ParseError::MissingArgForCommand(Box::from(control_seq_name)),
+            _ => e,
+        })?;
+        parse_color(model.as_deref(), &self.color_palette, color_name_group.into_iter())
+    }
+
+    /// Parses the arguments of `\definecolor{name}{model}{specification}` and adds the color to the palette
+    fn parse_color_definition(&mut self, control_seq_name : &str) -> ParseResult<()> {
+        let name  = tokens_as_string(self.capture_control_seq_group(control_seq_name)?.into_iter())?;
+        let model = tokens_as_string(self.capture_control_seq_group(control_seq_name)?.into_iter())?;
+        let specification = self.capture_control_seq_group(control_seq_name)?;
+        let color = parse_color(Some(&model), &self.color_palette, specification.into_iter())?;
+        self.color_palette.define(name.trim(), color);
+        Ok(())
+    }
+
+    /// Parses the dimension following commands like `\kern` ; it is either given in braces (`\hspace{1cm}`) or directly (`\mkern-3mu`).
+    fn parse_dimension_argument(&mut self, control_seq_name : &str) -> ParseResult<AnyUnit> {
+        let mut dimension = String::new();
+        let mut unit_length = 0;
+        while unit_length < 2 {
+            match self.token_iter.peek_token()? {
+                Some(TexToken::BeginGroup) if dimension.is_empty() => {
+                    let group = self.token_iter.capture_group()?;
+                    dimension = tokens_as_string(group.into_iter())?;
+                    break;
+                },
+                Some(TexToken::WhiteSpace) => (),
+                Some(TexToken::Char(c)) if c.is_ascii_alphabetic() && !dimension.is_empty() => {
+                    dimension.push(c);
+                    unit_length += 1;
+                },
+                Some(TexToken::Char(c)) if unit_length == 0 && (c.is_ascii_digit() || "+-.".contains(c)) => dimension.push(c),
+                _ => break,
+            }
+            self.token_iter.next_token()?;
+        }
+
+        if dimension.is_empty() {
+            return Err(ParseError::MissingArgForCommand(Box::from(control_seq_name)));
+        }
+        parse_dimension(&dimension)
+    }
+
+    /// Parses an argument of `\sideset{..}{..}`, which contains a superscript and/or a subscript (e.g. `_a^b`), and returns them in that order.
+    fn parse_sideset_scripts(&mut self, control_seq_name : &str) -> ParseResult<Labels> {
+        let group = self.capture_control_seq_group(control_seq_name)?;
+        let mut nodes = self.parse_tokens_as_nodes(group)?;
+        match nodes.pop() {
+            None => Ok((None, None)),
+            Some(ParseNode::Scripts(Scripts { base: None, superscript, subscript, .. })) if nodes.is_empty() 
+                => Ok((superscript, subscript)),
+            Some(_) => Err(ParseError::ExpectedScriptsForSideset),
+        }
+    }
+
+    fn capture_control_seq_group(&mut self, control_seq_name : &str) -> ParseResult<Vec<TexToken<'a>>> {
+        self.token_iter.capture_group().map_err(|e| match e {
+            ParseError::ExpectedToken => ParseError::MissingArgForCommand(Box::from(control_seq_name)),
+            _ => e,
+        })
+    }
+
+    /// Parses a delimiter argument of `\genfrac{..}{..}{..}{..}{..}{..}` ; an empty argument or `.` means no delimiter.
+    fn parse_fraction_delimiter(&mut self, control_seq_name : &str) -> ParseResult<Option<Symbol>> {
+        let group = self.capture_control_seq_group(control_seq_name)?;
+        match self.parse_tokens_as_nodes(group)?.as_slice() {
+            [] => Ok(None),
+            [ParseNode::Symbol(Symbol { codepoint: '.', .. })] => Ok(None),
+            [ParseNode::Symbol(symbol)] if matches!(symbol.atom_type, TexSymbolType::Open | TexSymbolType::Close | TexSymbolType::Fence) 
+                => Ok(Some(*symbol)),
+            _ => Err(ParseError::ExpectedDelimiter),
+        }
+    }
+
     fn parse_next_token_as_delimiter(&mut self) -> ParseResult<Symbol> {
         let token = self.token_iter.next_token()?.ok_or_else(|| ParseError::ExpectedSymbolAfterDelimiterCommand)?;
         match token {
@@ -546,11 +1000,49 @@ impl<'a, I : Iterator<Item = TexToken<'a>>> Parser<'a, I> {
 
     fn parse_required_argument_as_nodes(&mut self) -> ParseResult<Vec<ParseNode>> {
         let group = self.token_iter.capture_group()?;
+        self.parse_tokens_as_nodes(group)
+    }
+
+    /// Parses the `^{..}` and `_{..}` following a construction which takes them as labels, e.g. `\underbrace{..}_{..}`.
+    fn parse_labels(&mut self) -> ParseResult<Labels> {
+        let mut over  = None;
+        let mut under = None;
+        loop {
+            let label = match self.token_iter.peek_token()? {
+                Some(TexToken::WhiteSpace) => { self.token_iter.next_token()?; continue },
+                Some(TexToken::Superscript) => &mut over,
+                Some(TexToken::Subscript)   => &mut under,
+                _ => break,
+            };
+            if label.is_some() {
+                return Err(ParseError::TooManySubscriptsOrSuperscripts);
+            }
+            self.token_iter.next_token()?;
+            let nodes = self.parse_required_argument_as_nodes().map_err(|e| match e {
+                ParseError::ExpectedToken => ParseError::MissingSubSuperScript,
+                e => e,
+            })?;
+            *label = Some(nodes);
+        }
+        Ok((over, under))
+    }
 
+    /// Parses an optional argument delimited by square brackets, e.g. the `[3]` in `\sqrt[3]{x}`.
+    /// Returns `None` if the next token is not an opening square bracket.
+    fn parse_optional_argument_as_nodes(&mut self) -> ParseResult<Option<Vec<ParseNode>>> {
+        match self.token_iter.capture_optional_group()? {
+            Some(group) => Ok(Some(self.parse_tokens_as_nodes(group)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn parse_tokens_as_nodes(&self, group: Vec<TexToken<'a>>) -> ParseResult<Vec<ParseNode>> {
         // Normally all tokens are already expanded after `capture_group`
         // There is no need to have further expansions
         let mut forked_parser = Parser::from_iter(Self::EMPTY_COMMAND_COLLECTION, group.into_iter());
         forked_parser.current_style = self.current_style;
+        forked_parser.poor_mans_bold = self.poor_mans_bold;
+        forked_parser.color_palette = self.color_palette.clone();
 
         let List { nodes, group } = forked_parser.parse_until_end_of_group()?;
 
@@ -563,20 +1055,44 @@ impl<'a, I : Iterator<Item = TexToken<'a>>> Parser<'a, I> {
 }
 
 fn make_operator(op_name: &str, limits_placement: bool) -> ParseNode {
+    // The name is a text run, so that it is set in the text font if there is one
     ParseNode::AtomChange(nodes::AtomChange {
         at: TexSymbolType::Operator(limits_placement),
-        inner: 
-            op_name
-            .chars()
-            .map(|c| ParseNode::Symbol(Symbol {
-                codepoint: c,
-                atom_type: TexSymbolType::Ordinary,
-            }))
-            .collect()
-        ,
+        inner: vec![ParseNode::PlainText(nodes::PlainText { text: op_name.to_string(), style: text::TEXT_STYLE })],
     })
 }
 
+/// Turns the nodes of an argument into a single node, grouping them if needed.
+fn as_single_node(mut nodes: Vec<ParseNode>) -> ParseNode {
+    if nodes.len() == 1 {
+        nodes.pop().unwrap()
+    }
+    else {
+        ParseNode::Group(nodes)
+    }
+}
+
+/// An upright letter or digit, as in chemical formulae and unit names.
+fn upright(codepoint : char) -> ParseNode {
+    ParseNode::Symbol(Symbol { codepoint, atom_type: TexSymbolType::Alpha })
+}
+
+/// The content of a sub- or superscript: upright letters and digits, and signs which are not binary operators (as in the charge `2-`)
+fn script_nodes(script : &str) -> ParseResult<Vec<ParseNode>> {
+    script.chars()
+        .filter(|c| *c != ' ')
+        .map(|c| match c {
+            '+' => Ok(ParseNode::Symbol(Symbol { codepoint: '+', atom_type: TexSymbolType::Ordinary })),
+            '-' => Ok(ParseNode::Symbol(Symbol { codepoint: '−', atom_type: TexSymbolType::Ordinary })),
+            c if c.is_alphanumeric() => Ok(upright(c)),
+            c => {
+                let atom_type = codepoint_atom_type(c).ok_or(ParseError::UnrecognizedSymbol(c))?;
+                Ok(ParseNode::Symbol(Symbol { codepoint: c, atom_type }))
+            },
+        })
+        .collect()
+}
+
 /// Parses the input as a dimension, e.g. `1cm` or `-2pt or `3.5em`
 fn parse_dimension(input_string: &str) -> ParseResult<AnyUnit> {
     fn is_float_char(character : &char) -> bool {
@@ -597,7 +1113,17 @@ fn parse_dimension(input_string: &str) -> ParseResult<AnyUnit> {
 
     match dim {
         "em" => Ok(AnyUnit::Em(number)),
+        "ex" => Ok(AnyUnit::Ex(number)),
+        "mu" => Ok(AnyUnit::Mu(number)),
         "px" => Ok(AnyUnit::Px(number)),
+        "pt" => Ok(AnyUnit::Pt(number)),
+        "bp" => Ok(AnyUnit::Bp(number)),
+        "pc" => Ok(AnyUnit::Pc(number)),
+        "dd" => Ok(AnyUnit::Dd(number)),
+        "sp" => Ok(AnyUnit::Sp(number)),
+        "cm" => Ok(AnyUnit::Cm(number)),
+        "mm" => Ok(AnyUnit::Mm(number)),
+        "in" => Ok(AnyUnit::In(number)),
         _ => Err(ParseError::UnrecognizedDimension(Box::from(input_string))),
     }
 }
@@ -644,6 +1170,11 @@ pub fn parse_with_custom_commands<'a>(input: & 'a str, custom_commands : &Comman
     Parser::new(custom_commands, input).parse()
 }
 
+/// Like [`parse_with_custom_commands`], but with named colors (cf [`ColorPalette::parse`]) in addition to the macro collection.
+pub fn parse_with_custom_commands_and_colors(input: &str, custom_commands : &CommandCollection, color_palette : &ColorPalette) -> ParseResult<Vec<ParseNode>> {
+    Parser::new(custom_commands, input).with_color_palette(color_palette).parse()
+}
+
 
 
 
@@ -696,6 +1227,103 @@ mod tests {
         insta::assert_debug_snapshot!(parse(r"\frac\alpha\beta"));
     }
 
+    #[test]
+    fn snapshot_generalized_fractions() {
+        // success
+        insta::assert_debug_snapshot!(parse(r"\genfrac(]{0pt}{2}{a}{b}"));
+        insta::assert_debug_snapshot!(parse(r"\genfrac{\langle}{.}{}{}{a}{b}"));
+        insta::assert_debug_snapshot!(parse(r"\cfrac[l]{1}{2+\cfrac{1}{3}}"));
+        insta::assert_debug_snapshot!(parse(r"x + {a \over b} + {n \choose k} + {1 \atop 2}"));
+        insta::assert_debug_snapshot!(parse(r"\left( a+b \over c \right) \over 2"));
+
+        // failure
+        insta::assert_debug_snapshot!(parse(r"\genfrac(){}{4}{a}{b}"));
+        insta::assert_debug_snapshot!(parse(r"\genfrac a){}{}{a}{b}"));
+        insta::assert_debug_snapshot!(parse(r"\cfrac[x]{1}{2}"));
+        insta::assert_debug_snapshot!(parse(r"a \over b \atop c"));
+    }
+
+    #[test]
+    fn snapshot_modular_arithmetic() {
+        // success
+        insta::assert_debug_snapshot!(parse(r"a \bmod b"));
+        insta::assert_debug_snapshot!(parse(r"a \equiv b \pmod{m}"));
+        insta::assert_debug_snapshot!(parse(r"a \equiv b \pod{m} \mod{n}"));
+
+        // failure
+        insta::assert_debug_snapshot!(parse(r"a \equiv b \pmod"));
+    }
+
+    #[test]
+    fn snapshot_prescripts() {
+        // success
+        insta::assert_debug_snapshot!(parse(r"\sideset{_a^b}{_c^d}\sum"));
+        insta::assert_debug_snapshot!(parse(r"\sideset{}{^*}\prod_{n \geq 1}"));
+        insta::assert_debug_snapshot!(parse(r"\prescript{14}{6}{C}"));
+        insta::assert_debug_snapshot!(parse(r"\prescript{}{2}{F}_1"));
+
+        // failure
+        insta::assert_debug_snapshot!(parse(r"\sideset{a}{}\sum"));
+        insta::assert_debug_snapshot!(parse(r"\prescript{14}{6}"));
+    }
+
+    #[test]
+    fn snapshot_chemistry() {
+        // success
+        insta::assert_debug_snapshot!(parse(r"\ce{H2O}"));
+        insta::assert_debug_snapshot!(parse(r"\ce{2H2 + O2 -> 2H2O}"));
+        insta::assert_debug_snapshot!(parse(r"\ce{Ca(OH)2 + SO4^2- <=> CaSO4 v + 2OH-}"));
+        insta::assert_debug_snapshot!(parse(r"\ce{NaCl(s) ->[H2O] Na+(aq) + Cl-(aq)}"));
+        insta::assert_debug_snapshot!(parse(r"\ce{^{14}_{6}C -> ^{14}_{7}N + e- ^}"));
+
+        // failure
+        insta::assert_debug_snapshot!(parse(r"\ce{H2O ->[heat}"));
+        insta::assert_debug_snapshot!(parse(r"\ce{\alpha}"));
+        insta::assert_debug_snapshot!(parse(r"\ce{SO4^2-^3}"));
+    }
+
+    #[test]
+    fn snapshot_physical_units() {
+        // success
+        insta::assert_debug_snapshot!(parse(r"\num{-1234567.89012}"));
+        insta::assert_debug_snapshot!(parse(r"\num{1.5e-3} \num{e10} \num{2x3}"));
+        insta::assert_debug_snapshot!(parse(r"\num{,5+-0.2e3}"));
+        insta::assert_debug_snapshot!(parse(r"\si{\kilo\metre\per\second\squared}"));
+        insta::assert_debug_snapshot!(parse(r"\unit{kg.m/s^2}"));
+        insta::assert_debug_snapshot!(parse(r"\SI{9.81}{\metre\per\square\second} \qty{30}{\degree}"));
+
+        // failure
+        insta::assert_debug_snapshot!(parse(r"\num{1.2.3}"));
+        insta::assert_debug_snapshot!(parse(r"\si{\furlong}"));
+        insta::assert_debug_snapshot!(parse(r"\si{\metre\per}"));
+        insta::assert_debug_snapshot!(parse(r"\si{\metre\squared\cubed}"));
+    }
+
+    #[test]
+    fn snapshot_math_choice() {
+        // success
+        insta::assert_debug_snapshot!(parse(r"\mathchoice{D}{T}{S}{SS}"));
+        insta::assert_debug_snapshot!(parse(r"x^{\mathchoice{\sum}{\textstyle\sum}{}{}}"));
+
+        // failure
+        insta::assert_debug_snapshot!(parse(r"\mathchoice{D}{T}{S}"));
+    }
+
+    #[test]
+    fn snapshot_bold_symbols() {
+        // success
+        insta::assert_debug_snapshot!(parse(r"\boldsymbol{x\alpha\Gamma 2}"));
+        insta::assert_debug_snapshot!(parse(r"\bm{\nabla\partial\varepsilon} \mathbf{\partial}"));
+        insta::assert_debug_snapshot!(parse(r"\boldsymbol{\sum_i (a + b)}"));
+        insta::assert_debug_snapshot!(parse(r"\pmb{x+1}"));
+
+        // failure
+        insta::assert_debug_snapshot!(parse(r"\boldsymbol"));
+
+        // a single atom keeps its class
+        insta::assert_debug_snapshot!(parse(r"a\boldsymbol{=}b"));
+    }
+
     #[test]
     fn snapshot_radicals() {
         // success
@@ -712,6 +1340,14 @@ mod tests {
         insta::assert_debug_snapshot!(parse(r"\sqrt" ));
         insta::assert_debug_snapshot!(parse(r"\sqrt_2" ));
         insta::assert_debug_snapshot!(parse(r"\sqrt^2"));
+
+        // with a degree
+        insta::assert_debug_snapshot!(parse(r"\sqrt[3]{x}"));
+        insta::assert_debug_snapshot!(parse(r"\sqrt[n+1]\alpha"));
+        insta::assert_debug_snapshot!(parse(r"\sqrt[\sqrt[3]2]{x}"));
+        insta::assert_debug_snapshot!(parse(r"\sqrt[]{x}"));
+        insta::assert_debug_snapshot!(parse(r"x^\sqrt[3]2"));
+        insta::assert_debug_snapshot!(parse(r"\sqrt[3{x}"));
     }
 
 
@@ -765,6 +1401,73 @@ mod tests {
         insta::assert_debug_snapshot!(parse(r"\begin{array}{rl@} 1 & 2 \\ 3 & 4\end{array}"));
     }
 
+    #[test]
+    fn snapshot_array_lines() {
+        insta::assert_debug_snapshot!(parse(r"\begin{array}{cc}\hline 1 & 2 \\ \hline\hline 3 & 4 \\ \hline\end{array}"));
+        insta::assert_debug_snapshot!(parse(r"\begin{array}{ccc}1 & 2 & 3 \\ \cline{2-3} 4 & 5 & 6\end{array}"));
+        insta::assert_debug_snapshot!(parse(r"\begin{array}{cc}\hdashline 1 & 2 \\ \cdashline{1-1} 3 & 4\end{array}"));
+
+        // fail
+        insta::assert_debug_snapshot!(parse(r"\begin{array}{cc}1 & 2 \\ \cline{3} 3 & 4\end{array}"));
+        insta::assert_debug_snapshot!(parse(r"\begin{array}{cc}1 & 2 \\ \cline{2-1} 3 & 4\end{array}"));
+        insta::assert_debug_snapshot!(parse(r"\begin{array}{cc}1 & \hline 2\end{array}"));
+        insta::assert_debug_snapshot!(parse(r"\begin{array}{cc}1 & 2 \\ \cline{2-3} 3 & 4\end{array}"));
+    }
+
+    #[test]
+    fn snapshot_cases() {
+        insta::assert_debug_snapshot!(parse(r"\begin{cases}1 & x > 0 \\ 0 & \text{otherwise}\end{cases}"));
+        insta::assert_debug_snapshot!(parse(r"\begin{dcases}\frac12 & x > 0\end{dcases}"));
+        insta::assert_debug_snapshot!(parse(r"\begin{rcases}a \\ b\end{rcases}"));
+        insta::assert_debug_snapshot!(parse(r"\begin{cases}1 & 2\end{dcases}"));
+    }
+
+    #[test]
+    fn snapshot_aligned_environments() {
+        insta::assert_debug_snapshot!(parse(r"\begin{gathered}a = b \\ c = d + e\end{gathered}"));
+        insta::assert_debug_snapshot!(parse(r"\begin{alignedat}{2}a &= b & c &= d \\ e &= f & g &= h\end{alignedat}"));
+        insta::assert_debug_snapshot!(parse(r"\begin{split}a &= b + c \\ &= d\end{split}"));
+        insta::assert_debug_snapshot!(parse(r"\begin{smallmatrix}a & b \\ c & d\end{smallmatrix}"));
+
+        // fail
+        insta::assert_debug_snapshot!(parse(r"\begin{alignedat}a &= b\end{alignedat}"));
+        insta::assert_debug_snapshot!(parse(r"\begin{alignedat}"));
+        insta::assert_debug_snapshot!(parse(r"\begin{alignedat}{0} a \end{alignedat}"));
+    }
+
+    #[test]
+    fn snapshot_macro_definitions() {
+        // success
+        insta::assert_debug_snapshot!(parse(r"\newcommand{\R}{\mathbb{R}} x \in \R"));
+        insta::assert_debug_snapshot!(parse(r"\newcommand*\pair[2]{(#1, #2)} \pair ab"));
+        insta::assert_debug_snapshot!(parse(r"\def\pair#1#2{(#1, #2)} \pair ab"));
+        insta::assert_debug_snapshot!(parse(r"\newcommand\f[1]{f(#1)} {\renewcommand\f[1]{g(#1)} \f x} \f x"));
+        insta::assert_debug_snapshot!(parse(r"\def\x{1}\renewcommand\x{\x+1}\x"));
+        insta::assert_debug_snapshot!(parse(r"\frac{\def\x{1}\x}{2}"));
+        insta::assert_debug_snapshot!(parse(r"\renewcommand\alpha{a}\alpha"));
+
+        // fail
+        insta::assert_debug_snapshot!(parse(r"{\def\x{1}} \x"));
+        insta::assert_debug_snapshot!(parse(r"\newcommand\x{1}\newcommand\x{2}"));
+        insta::assert_debug_snapshot!(parse(r"\renewcommand\x{1}"));
+        insta::assert_debug_snapshot!(parse(r"\def\x#1.{#1}"));
+        insta::assert_debug_snapshot!(parse(r"\def\x#2{#2}"));
+        insta::assert_debug_snapshot!(parse(r"\newcommand\x[1]{#2}"));
+
+        // optional first argument
+        insta::assert_debug_snapshot!(parse(r"\newcommand\seq[2][n]{#2_1, \dots, #2_#1} \seq x \seq[m]y"));
+        insta::assert_debug_snapshot!(parse(r"\newcommand\x[0][a]{b}"));
+
+        // bodies are expanded at use, and recursive macros fail
+        insta::assert_debug_snapshot!(parse(r"\def\a{x}\def\b{\a}\def\a{y}\b"));
+        insta::assert_debug_snapshot!(parse(r"\def\x{\x}\x"));
+        insta::assert_debug_snapshot!(parse(r"\newcommand\a{\b}\newcommand\b{\a}\a"));
+
+        // primitives cannot be redefined with \newcommand
+        insta::assert_debug_snapshot!(parse(r"\newcommand{\frac}{x}"));
+        insta::assert_debug_snapshot!(parse(r"\DeclareMathOperator{\sin}{sine}"));
+    }
+
     #[ignore = "unsupported as of yet"]
     #[test]
     fn snapshot_rule() {
@@ -781,6 +1484,27 @@ mod tests {
         insta::assert_debug_snapshot!(parse(r"\text{a{\}}}"));
     }
 
+    #[test]
+    fn snapshot_rich_text() {
+        // success
+        insta::assert_debug_snapshot!(parse(r"\text{if $x>0$}"));
+        insta::assert_debug_snapshot!(parse(r"\text{\textbf{bold} and \textit{\textbf {both}}}"));
+        insta::assert_debug_snapshot!(parse(r"\text{50\% \& \$5 \_\#1}"));
+        insta::assert_debug_snapshot!(parse(r"\text{a \(x^2\) b}^2"));
+        insta::assert_debug_snapshot!(parse(r"\textsf{sans} \texttt{mono 1} \textrm{\textbf{ro}man}"));
+        insta::assert_debug_snapshot!(parse(r"\mbox{$a$ b} \fbox{area $\pi r^2$}"));
+
+        // failure
+        insta::assert_debug_snapshot!(parse(r"\text{if $x>0}"));
+        insta::assert_debug_snapshot!(parse(r"\text{\alpha}"));
+        insta::assert_debug_snapshot!(parse(r"\text{\textbf x}"));
+        insta::assert_debug_snapshot!(parse(r"\text{a^2}"));
+
+        // only letters and digits are styled, and math may nest text with math
+        insta::assert_debug_snapshot!(parse(r"\textbf{log-in 2}"));
+        insta::assert_debug_snapshot!(parse(r"\text{$\text{$x$}$}"));
+    }
+
     #[test]
     fn snapshot_mbox() {
         insta::assert_debug_snapshot!(parse(r"\mbox{abc}"));
@@ -805,6 +1529,46 @@ mod tests {
         insta::assert_debug_snapshot!(parse(r"\color red{1}"));
     }
 
+    #[test]
+    fn snapshot_color_syntax() {
+        // success
+        insta::assert_debug_snapshot!(parse(r"\color{#00ff00}{a} \color{#123456}{b} \color{#f80}{c} \color{#ff000080}{d}"));
+        insta::assert_debug_snapshot!(parse(r"\color{rgb(255, 128, 0)}{a} \color{rgba(255, 128, 0, 0.5)}{b}"));
+        insta::assert_debug_snapshot!(parse(r"\color[rgb]{1,0.5,0}{a} \color[RGB]{255,128,0}{b} \color[HTML]{FF8000}{c}"));
+        insta::assert_debug_snapshot!(parse(r"\color[gray]{0.5}{a} \color[cmyk]{0,0.5,1,0}{b} \color[cmy]{0,0.5,1}{c}"));
+        insta::assert_debug_snapshot!(parse(r"\color{red!30!blue}{a} \color{red!30}{b} \color{red!50!blue!50!green}{c} \color{-red}{d}"));
+        insta::assert_debug_snapshot!(parse(r"\fcolorbox[rgb]{1,0,0}[HTML]{00FF00}{a}"));
+
+        // failure
+        insta::assert_debug_snapshot!(parse(r"\color{#12345}{a}"));
+        insta::assert_debug_snapshot!(parse(r"\color[rgb]{1,2,3}{a}"));
+        insta::assert_debug_snapshot!(parse(r"\color[hsb]{0,0,0}{a}"));
+        insta::assert_debug_snapshot!(parse(r"\color{red!130!blue}{a}"));
+        insta::assert_debug_snapshot!(parse(r"\color{\alpha}{a}"));
+    }
+
+    #[test]
+    fn snapshot_color_switch_and_definitions() {
+        // success
+        insta::assert_debug_snapshot!(parse(r"{\color{red} a + b} c"));
+        insta::assert_debug_snapshot!(parse(r"a \color[rgb]{0,0,1} b \over c"));
+        insta::assert_debug_snapshot!(parse(r"\textcolor{red}{x} y"));
+        insta::assert_debug_snapshot!(parse(r"\definecolor{accent}{HTML}{FF8000} \color{accent} x \textcolor{accent!50!blue}{y}"));
+        insta::assert_debug_snapshot!(parse(r"\definecolor{red}{gray}{0.5} \textcolor{red}{x}"));
+        let palette = ColorPalette::parse(r"\definecolor{accent}{rgb}{1,0.5,0} \definecolor{muted}{gray}{0.5}").unwrap();
+        insta::assert_debug_snapshot!(parse_with_custom_commands_and_colors(r"\textcolor{accent}{x} \textcolor{muted}{y}", &CommandCollection::new(), &palette));
+
+        // failure
+        insta::assert_debug_snapshot!(parse(r"{\definecolor{accent}{HTML}{FF8000}} \textcolor{accent}{x}"));
+        insta::assert_debug_snapshot!(parse(r"\definecolor{accent}{rgb}{1,0.5}"));
+        insta::assert_debug_snapshot!(parse(r"\textcolor{red}"));
+        insta::assert_debug_snapshot!(ColorPalette::parse(r"\definecolor{accent}{rgb}{1,0.5,0} x"));
+        insta::assert_debug_snapshot!(ColorPalette::parse(r"\definecolor{muted}{accent}{0.5}"));
+        insta::assert_debug_snapshot!(parse(r"a \over b \color{red} c \over d"));
+        insta::assert_debug_snapshot!(ColorPalette::parse(r"\def\x{y}"));
+        insta::assert_debug_snapshot!(ColorPalette::parse(r"\definecolor{accent}{gray}{0.5} \newcommand{\x}{y}"));
+    }
+
 
 
     #[test]
@@ -843,6 +1607,21 @@ mod tests {
         insta::assert_debug_snapshot!(parse(r"1\33"));
     }
 
+    #[test]
+    fn snapshot_spacing_commands() {
+        // success
+        insta::assert_debug_snapshot!(parse(r"a\kern3pt b\hskip -2.5 mm c"));
+        insta::assert_debug_snapshot!(parse(r"a\mkern 4mu b\mskip-3mu c"));
+        insta::assert_debug_snapshot!(parse(r"a\hspace{1cm}b\hspace*{2ex}c"));
+        insta::assert_debug_snapshot!(parse(r"\kern1in\kern2bp\kern3pc\kern4dd\kern5sp\kern6em\kern7px"));
+
+        // failure
+        insta::assert_debug_snapshot!(parse(r"\mkern3pt"));
+        insta::assert_debug_snapshot!(parse(r"\kern3mu"));
+        insta::assert_debug_snapshot!(parse(r"\hspace{3xx}"));
+        insta::assert_debug_snapshot!(parse(r"a\kern"));
+    }
+
     #[test]
     fn snapshot_delimiter() {
         // success
@@ -874,6 +1653,68 @@ mod tests {
         insta::assert_debug_snapshot!(parse(r"\substack{ 1 \\ 1"));
     }
 
+    #[test]
+    fn snapshot_over_under() {
+        // success
+        insta::assert_debug_snapshot!(parse(r"\overset{!}{=}"));
+        insta::assert_debug_snapshot!(parse(r"\underset{n}{x+y}"));
+        insta::assert_debug_snapshot!(parse(r"a \stackrel{\mathrm{def}}{=} b"));
+        insta::assert_debug_snapshot!(parse(r"\underbrace{a+b}_{n}"));
+        insta::assert_debug_snapshot!(parse(r"\overbrace{a+b} ^n_m"));
+        insta::assert_debug_snapshot!(parse(r"\overbrace{a+b}^n^m"));
+
+        // failure
+        insta::assert_debug_snapshot!(parse(r"\overset{!}"));
+        insta::assert_debug_snapshot!(parse(r"\underbrace{a+b}_"));
+    }
+
+    #[test]
+    fn snapshot_extensible_arrows() {
+        // success
+        insta::assert_debug_snapshot!(parse(r"A \xrightarrow{f} B"));
+        insta::assert_debug_snapshot!(parse(r"A \xleftarrow[g]{f} B"));
+        insta::assert_debug_snapshot!(parse(r"x \xmapsto{} y"));
+        insta::assert_debug_snapshot!(parse(r"p \xLeftrightarrow[\text{iff}]{\text{def}} q"));
+
+        // failure
+        insta::assert_debug_snapshot!(parse(r"A \xrightarrow[g]"));
+    }
+
+    #[test]
+    fn snapshot_framed() {
+        // success
+        insta::assert_debug_snapshot!(parse(r"\boxed{x^2}"));
+        insta::assert_debug_snapshot!(parse(r"\fbox{some text}"));
+        insta::assert_debug_snapshot!(parse(r"\colorbox{yellow}{a}"));
+        insta::assert_debug_snapshot!(parse(r"\fcolorbox{red}{lime}{a}"));
+
+        // failure
+        insta::assert_debug_snapshot!(parse(r"\colorbox{yellow}"));
+    }
+
+    #[test]
+    fn snapshot_resized_box() {
+        // success
+        insta::assert_debug_snapshot!(parse(r"a\phantom{x}b"));
+        insta::assert_debug_snapshot!(parse(r"\hphantom{x}\vphantom{y}"));
+        insta::assert_debug_snapshot!(parse(r"\smash{y} \smash[t]{y} \smash[b]{y}"));
+        insta::assert_debug_snapshot!(parse(r"\mathllap{a}\mathrlap{b}\mathclap{c}"));
+
+        // failure
+        insta::assert_debug_snapshot!(parse(r"\smash[x]{y}"));
+    }
+
+    #[test]
+    fn snapshot_cancel() {
+        // success
+        insta::assert_debug_snapshot!(parse(r"\cancel{x}"));
+        insta::assert_debug_snapshot!(parse(r"a + \bcancel{b} + \xcancel{c}"));
+        insta::assert_debug_snapshot!(parse(r"\cancelto{0}{x-x}"));
+
+        // failure
+        insta::assert_debug_snapshot!(parse(r"\cancelto{0}"));
+    }
+
     #[test]
     fn snapshot_style_change() {
         // success
@@ -897,6 +1738,12 @@ mod tests {
         
         // failure
         insta::assert_debug_snapshot!(parse(r"\operatorname{\frac12}"));
+
+        // with limits
+        insta::assert_debug_snapshot!(parse(r"\operatorname*{argmax}_x"));
+        insta::assert_debug_snapshot!(parse(r"\DeclareMathOperator{\Tr}{Tr} \Tr_x"));
+        insta::assert_debug_snapshot!(parse(r"\DeclareMathOperator*{\argmin}{arg min} \argmin_x"));
+        insta::assert_debug_snapshot!(parse(r"\DeclareMathOperator{\Tr}{Tr} \DeclareMathOperator{\Tr}{tr}"));
     }
 
     #[test]
@@ -2,6 +2,7 @@
 
 use crate::dimensions::AnyUnit;
 use crate::layout::{self, Style};
+use crate::font::Style as FontStyle;
 use super::color::RGBA;
 use crate::font::TexSymbolType;
 use super::symbols::Symbol;
@@ -28,18 +29,34 @@ pub enum ParseNode {
     Rule(Rule),
     /// Some (positive or negative) spacing between groups of nodes
     Kerning(AnyUnit),
+    /// Some spacing whose size depends on the style at layout time (e.g. the space before `\pmod{..}`, wider in display style)
+    StyledKerning(StyledKerning),
+    /// Four alternatives of which one is chosen at layout time, according to the style (created by `\mathchoice{..}{..}{..}{..}`)
+    MathChoice(MathChoice),
     /// An accent over a certain groups of nodes
     Accent(Accent),
+    /// Nodes with a label placed above or below them (e.g. `\overset{..}{..}` or `\underbrace{..}_{..}`)
+    OverUnder(OverUnder),
+    /// A horizontally stretched arrow with labels above and below it (e.g. `\xrightarrow[..]{..}`)
+    ExtensibleArrow(ExtensibleArrow),
     /// A style (text cramped) to apply over a certain group of nodes
     Style(Style),
     /// Underline and overlines
     FontEffect(FontEffect),
+    /// Nodes emboldened by overstriking offset copies of them (created by `\pmb{..}`, and by `\boldsymbol{..}` for symbols without a bold counterpart)
+    PoorMansBold(PoorMansBold),
     /// A span of normal text without special math symbol replacement, spacing, etc.
     PlainText(PlainText),
     /// A change in the type of atoms
     AtomChange(AtomChange),
     /// A change in color
     Color(Color),
+    /// A group of nodes whose box dimensions are changed or which is not drawn (e.g. `\phantom{..}`, `\smash{..}` or `\mathrlap{..}`)
+    ResizedBox(ResizedBox),
+    /// A group of nodes struck out by diagonal strokes (e.g. `\cancel{..}` or `\cancelto{..}{..}`)
+    Cancel(Cancel),
+    /// A group of nodes surrounded by a frame and/or drawn over a colored background (e.g. `\boxed{..}` or `\colorbox{..}{..}`)
+    Framed(Framed),
     /// A group of nodes
     Group(Vec<ParseNode>),
     /// Nodes stacked on top of each other with no alignment (the \substack command)
@@ -111,6 +128,10 @@ pub struct Array {
     /// A collection of rows.  Each row consists of one `Vec<Expression>`.
     pub rows: Vec<Vec<CellContent>>,
 
+    /// Horizontal lines between rows (e.g. `\hline`).  
+    /// `row_separators[i]` are the lines above row `i` ; the last element holds the lines below the last row.
+    pub row_separators: Vec<Vec<RowSeparator>>,
+
     /// The left delimiter for the array (optional).
     pub left_delimiter: Option<Symbol>,
 
@@ -126,6 +147,23 @@ pub struct Array {
     /// when they are embedded in an environment with text style.
     /// In `\begin{array} .. \end{array}`, they are rendered in text style
     pub cell_layout_style : layout::Style,
+
+    /// Half the horizontal space inserted between two columns, if different from LaTeX's `\arraycolsep`.  
+    /// In `\begin{smallmatrix} .. \end{smallmatrix}`, columns are closer to one another.
+    pub half_col_sep : Option<AnyUnit>,
+
+    /// Spacing between rows, if different from the one used in `\begin{array} .. \end{array}`.  
+    /// In `\begin{smallmatrix} .. \end{smallmatrix}`, rows are closer to one another.
+    pub row_spacing : Option<ArrayRowSpacing>,
+}
+
+/// Vertical spacing between the rows of an array. Cf [`Array::row_spacing`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ArrayRowSpacing {
+    /// Desired distance between two consecutive baselines (`\baselineskip`)
+    pub baseline_skip : AnyUnit,
+    /// Space inserted between two rows if they would otherwise come closer than that (`\lineskip`)
+    pub line_skip : AnyUnit,
 }
 
 /// An enum for the differents elements that can be used to separate columns in TeX.
@@ -144,6 +182,25 @@ impl ColSeparator {
     }
 }
 
+/// A horizontal line drawn between two rows of an array.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RowSeparator {
+    /// A line spanning all columns, as created by `\hline` (or `\hdashline` if dashed).
+    HorizontalLine {
+        /// Whether the line is dashed
+        dashed: bool,
+    },
+    /// A line spanning columns `first` to `last` (numbered from 1, inclusive), as created by `\cline{first-last}` (or `\cdashline{first-last}` if dashed).
+    PartialLine {
+        /// First column covered by the line
+        first: usize,
+        /// Last column covered by the line
+        last: usize,
+        /// Whether the line is dashed
+        dashed: bool,
+    },
+}
+
 
 /// Cf [`ParseNode::Stack`]
 #[derive(Debug, PartialEq, Clone)]
@@ -204,6 +261,10 @@ pub struct Scripts {
     pub superscript: Option<Vec<ParseNode>>,
     /// Subscripted nodes.
     pub subscript: Option<Vec<ParseNode>>,
+    /// Nodes placed as a superscript on the left of the base (e.g. with `\sideset{..}{..}` or `\prescript{..}{..}{..}`).
+    pub presuperscript: Option<Vec<ParseNode>>,
+    /// Nodes placed as a subscript on the left of the base.
+    pub presubscript: Option<Vec<ParseNode>>,
 }
 
 impl Scripts {
@@ -239,6 +300,8 @@ pub struct AtomChange {
 pub struct PlainText {
     /// Text to be renderered
     pub text: String,
+    /// Font of the text (e.g. bold in `\textbf{..}`), applied at layout time so that the text font and its ligatures can be used
+    pub style: FontStyle,
 }
 
 /// Cf [`ParseNode::Accent`]
@@ -254,6 +317,32 @@ pub struct Accent {
     pub under : bool,
 }
 
+/// Cf [`ParseNode::OverUnder`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct OverUnder {
+    /// The nodes the labels are attached to.
+    pub base: Vec<ParseNode>,
+    /// Label placed above the base, in script style.
+    pub over: Option<Vec<ParseNode>>,
+    /// Label placed below the base, in script style.
+    pub under: Option<Vec<ParseNode>>,
+    /// Whether the base is a stretched construction like `\overbrace{..}` ; the labels are then placed with the `StretchStack*` constants rather than the `UpperLimit*`/`LowerLimit*` ones.
+    pub stretchy: bool,
+    /// Atom type of the whole construction
+    pub atom_type: TexSymbolType,
+}
+
+/// Cf [`ParseNode::ExtensibleArrow`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExtensibleArrow {
+    /// Unicode codepoint of the arrow, stretched to be at least as wide as the labels.
+    pub arrow: char,
+    /// Label placed above the arrow, in script style.
+    pub over: Vec<ParseNode>,
+    /// Optional label placed below the arrow, in script style.
+    pub under: Option<Vec<ParseNode>>,
+}
+
 /// Cf [`ParseNode::Rule`]. While intended to be used as lines, rules can in fact be any rectangle.
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub struct Rule {
@@ -264,12 +353,53 @@ pub struct Rule {
     //pub depth:  Unit,
 }
 
+/// Cf [`ParseNode::MathChoice`]
+#[derive(Clone, PartialEq, Debug)]
+pub struct MathChoice {
+    /// nodes used in display style
+    pub display: Vec<ParseNode>,
+    /// nodes used in text style
+    pub text: Vec<ParseNode>,
+    /// nodes used in script style
+    pub script: Vec<ParseNode>,
+    /// nodes used in scriptscript style
+    pub scriptscript: Vec<ParseNode>,
+}
+
+impl MathChoice {
+    /// The nodes to lay out in the given style
+    pub fn choose(&self, style: Style) -> &[ParseNode] {
+        if style >= Style::DisplayCramped { &self.display }
+        else if style >= Style::TextCramped { &self.text }
+        else if style >= Style::ScriptCramped { &self.script }
+        else { &self.scriptscript }
+    }
+}
+
+/// Cf [`ParseNode::StyledKerning`]
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct StyledKerning {
+    /// spacing in display style
+    pub display: AnyUnit,
+    /// spacing in text style
+    pub text: AnyUnit,
+    /// spacing in script and scriptscript style
+    pub script: AnyUnit,
+}
+
 #[derive(Clone, PartialEq, Debug)]
 pub struct FontEffect {
     /// Nodes to be underlined
     pub inner : Vec<ParseNode>,
 }
 
+/// Cf [`ParseNode::PoorMansBold`]
+#[derive(Clone, PartialEq, Debug)]
+pub struct PoorMansBold {
+    /// Nodes to be emboldened
+    pub inner : Vec<ParseNode>,
+}
+
 /// Cf [`ParseNode::Radical`]
 #[derive(Debug, PartialEq, Clone)]
 pub struct Radical {
@@ -277,7 +407,8 @@ pub struct Radical {
     pub inner: Vec<ParseNode>,
     /// The character used to represent the radical (typically U+221A '√', but also '∛' U+221B, etc.)
     pub character: char,
-    // pub superscript: Vec<ParseNode>,
+    /// The index of the root, e.g. the `3` in `\sqrt[3]{x}`, if any.
+    pub degree: Option<Vec<ParseNode>>,
 }
 
 /// Cf [`ParseNode::GenFraction`]
@@ -295,6 +426,8 @@ pub struct GenFraction {
     pub right_delimiter: Option<Symbol>,
     /// style for the whole fraction.
     pub style: MathStyle,
+    /// placement of the numerator when it is narrower than the denominator (cf `\cfrac[l]{..}{..}`).
+    pub numerator_align: ArrayColumnAlign,
 }
 
 /// Cf [`ParseNode::Color`]
@@ -306,6 +439,71 @@ pub struct Color {
     pub inner: Vec<ParseNode>,
 }
 
+/// Cf [`ParseNode::Framed`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Framed {
+    /// Nodes placed inside the frame.
+    pub inner: Vec<ParseNode>,
+    /// Space between the inner nodes and the frame (`\fboxsep` in LaTeX).
+    pub padding: AnyUnit,
+    /// Thickness of the frame (`\fboxrule` in LaTeX) ; no frame is drawn if it is zero.
+    pub border_thickness: AnyUnit,
+    /// Color of the frame ; if `None`, the current color is used.
+    pub border_color: Option<RGBA>,
+    /// Color of the background ; if `None`, the background is left untouched.
+    pub fill_color: Option<RGBA>,
+}
+
+/// Cf [`ParseNode::ResizedBox`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResizedBox {
+    /// Nodes inside the box.
+    pub inner: Vec<ParseNode>,
+    /// Whether the nodes are drawn ; phantoms only take up the space of their nodes.
+    pub visible: bool,
+    /// Width of the box.
+    pub width: BoxWidth,
+    /// Whether the box keeps the height of its nodes ; otherwise its height is zero.
+    pub keep_height: bool,
+    /// Whether the box keeps the depth of its nodes ; otherwise its depth is zero.
+    pub keep_depth: bool,
+}
+
+/// Width of a [`ResizedBox`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BoxWidth {
+    /// The width of the nodes
+    Natural,
+    /// Zero width ; the nodes overlap what comes before the box (`\mathllap`)
+    OverlapLeft,
+    /// Zero width ; the nodes overlap what comes after the box (`\mathrlap`)
+    OverlapRight,
+    /// Zero width ; the nodes are centered on the position of the box (`\mathclap`)
+    OverlapCenter,
+}
+
+/// Cf [`ParseNode::Cancel`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cancel {
+    /// Nodes that are struck out.
+    pub inner: Vec<ParseNode>,
+    /// Direction of the strokes across the nodes.
+    pub kind: CancelKind,
+    /// For `\cancelto{..}{..}`, the value placed at the tip of the arrow, in script style.
+    pub to: Option<Vec<ParseNode>>,
+}
+
+/// Strokes drawn across the nodes of a [`Cancel`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CancelKind {
+    /// A stroke from the bottom left to the top right corner (`\cancel`)
+    Forward,
+    /// A stroke from the top left to the bottom right corner (`\bcancel`)
+    Backward,
+    /// Both strokes (`\xcancel`)
+    Cross,
+}
+
 /// Type of thickness for fraction and binomials
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum BarThickness {
@@ -324,6 +522,10 @@ pub enum MathStyle {
     Display,
     /// text style ; characters are straight.
     Text,
+    /// script style, as used for first-level sub- and superscripts.
+    Script,
+    /// scriptscript style, as used for second-level sub- and superscripts.
+    ScriptScript,
     /// keep style inherited from higher nodes.
     NoChange,
 }
@@ -374,19 +576,31 @@ impl ParseNode {
 
             ParseNode::Rule(_)          => TexSymbolType::Alpha,
             ParseNode::Kerning(_)       => TexSymbolType::Transparent,
+            ParseNode::StyledKerning(_) => TexSymbolType::Transparent,
+            // The branch is only known at layout time ; like a group, the chosen nodes form an ordinary atom
+            ParseNode::MathChoice(_)    => TexSymbolType::Alpha,
             ParseNode::Accent(ref acc) if acc.extend => TexSymbolType::Operator(true),
             ParseNode::Accent(ref acc) => 
                 acc.nucleus.first()
                 .map(|acc| acc.atom_type())
                 .unwrap_or(TexSymbolType::Alpha),
 
+            ParseNode::OverUnder(ref ou) => ou.atom_type,
+            ParseNode::ExtensibleArrow(_) => TexSymbolType::Relation,
             ParseNode::Style(_)         => TexSymbolType::Transparent,
             ParseNode::FontEffect(_)    => TexSymbolType::Under,
+            ParseNode::PoorMansBold(ref bold) => bold.inner.first()
+                .map(|first| first.atom_type())
+                .unwrap_or(TexSymbolType::Alpha),
             ParseNode::AtomChange(ref ac) => ac.at,
             ParseNode::Color(ref clr)     => clr.inner.first()
                 .map(|first| first.atom_type())
                 .unwrap_or(TexSymbolType::Alpha),
 
+            ParseNode::Framed(_)          => TexSymbolType::Alpha,
+            ParseNode::Cancel(_)          => TexSymbolType::Alpha,
+            ParseNode::ResizedBox(_)      => TexSymbolType::Alpha,
+
             ParseNode::Array(_)      => TexSymbolType::Inner,
             ParseNode::Stack(ref s)  => s.atom_type,
 
@@ -0,0 +1,154 @@
+//! Parses the argument of text commands like `\text{..}` or `\mbox{..}`.
+//!
+//! Text mode supports:
+//!   - runs of characters, where spaces and `~` are word spaces
+//!   - the font commands `\textbf{..}`, `\textit{..}`, `\textrm{..}`, `\textsf{..}` and `\texttt{..}`, which can be nested
+//!   - the escaped characters `\%`, `\&`, `\_`, `\$`, `\#`, `\{` and `\}`
+//!   - math, enclosed in `$..$` or `\(..\)`
+
+use std::vec::IntoIter;
+
+use crate::font::{Family, Style, Weight};
+
+use super::error::{ParseError, ParseResult};
+use super::nodes::PlainText;
+use super::textoken::{NumberOfPrimes, TexToken};
+use super::{ParseNode, Parser};
+
+/// The font of text which is not in the argument of a font command: upright, unlike math letters
+pub const TEXT_STYLE : Style = Style { family: Family::Roman, weight: Weight::None };
+
+/// The font resulting from a text font command like `\textbf{..}` applied to text in the given font
+pub fn text_font(command_name : &str, style : Style) -> Option<Style> {
+    Some(match command_name {
+        "textbf" => style.with_bold(),
+        "textit" => style.with_italics(),
+        "textrm" => style.with_family(Family::Roman),
+        "textsf" => style.with_family(Family::SansSerif),
+        "texttt" => style.with_family(Family::Monospace),
+        _ => return None,
+    })
+}
+
+impl<'a, I : Iterator<Item = TexToken<'a>>> Parser<'a, I> {
+    /// Parses the tokens of a text argument, set in the given font, into text runs and (grouped) math.
+    pub(super) fn parse_text(&self, tokens : Vec<TexToken<'a>>, style : Style) -> ParseResult<Vec<ParseNode>> {
+        let mut text_parser = TextParser {
+            parser : self,
+            tokens : tokens.into_iter(),
+            nodes : Vec::new(),
+            run : String::new(),
+            run_style : style,
+        };
+        text_parser.parse_group(style)?;
+
+        // An empty text still gives an (empty) text run
+        if !text_parser.run.is_empty() || text_parser.nodes.is_empty() {
+            text_parser.end_run();
+        }
+        Ok(text_parser.nodes)
+    }
+}
+
+struct TextParser<'p, 'a, I : Iterator<Item = TexToken<'a>>> {
+    parser : &'p Parser<'a, I>,
+    tokens : IntoIter<TexToken<'a>>,
+    nodes : Vec<ParseNode>,
+    /// The characters of the current text run
+    run : String,
+    /// The font of the current text run
+    run_style : Style,
+}
+
+impl<'a, I : Iterator<Item = TexToken<'a>>> TextParser<'_, 'a, I> {
+    /// Parses the tokens up to the end of the current group (or of the input), font changes being local to the group
+    fn parse_group(&mut self, style : Style) -> ParseResult<()> {
+        while let Some(token) = self.tokens.next() {
+            match token {
+                TexToken::Char('$') => {
+                    let math = self.capture_math(TexToken::Char('$'))?;
+                    self.push_math(math)?;
+                },
+                TexToken::Char(c) => self.push_str(c.encode_utf8(&mut [0; 4]), style),
+                TexToken::WhiteSpace | TexToken::Tilde => self.push_str(" ", style),
+                TexToken::Prime(number_of_primes) => {
+                    self.push_str(match number_of_primes {
+                        NumberOfPrimes::Simple => "'",
+                        NumberOfPrimes::Double => "''",
+                        NumberOfPrimes::Triple => "'''",
+                    }, style);
+                },
+                TexToken::BeginGroup => self.parse_group(style)?,
+                TexToken::EndGroup => return Ok(()),
+                TexToken::ControlSequence("(") => {
+                    let math = self.capture_math(TexToken::ControlSequence(")"))?;
+                    self.push_math(math)?;
+                },
+                TexToken::ControlSequence(" ") => self.push_str(" ", style),
+                TexToken::ControlSequence(escaped @ ("%" | "&" | "_" | "$" | "#" | "{" | "}")) => {
+                    self.push_str(escaped, style);
+                },
+                TexToken::ControlSequence(name) => {
+                    let font = text_font(name, style).ok_or_else(|| ParseError::UnrecognizedControlSequence(Box::from(name)))?;
+                    // Spaces following a command name are ignored
+                    let argument = match self.tokens.next() {
+                        Some(TexToken::WhiteSpace) => self.tokens.next(),
+                        token => token,
+                    };
+                    if argument != Some(TexToken::BeginGroup) {
+                        return Err(ParseError::MissingArgForCommand(Box::from(name)));
+                    }
+                    self.parse_group(font)?;
+                },
+                  TexToken::Superscript
+                | TexToken::Subscript
+                | TexToken::Alignment
+                | TexToken::Argument(_)
+                => return Err(ParseError::ExpectedChars),
+            }
+        }
+        Ok(())
+    }
+
+    /// Captures the tokens of inline math up to the closing token (`$` or `\)`), ignoring those inside groups (e.g. in `$\text{$x$}$`)
+    fn capture_math(&mut self, closing : TexToken<'a>) -> ParseResult<Vec<TexToken<'a>>> {
+        let mut math = Vec::new();
+        let mut n_open_paren : u32 = 0;
+        for token in self.tokens.by_ref() {
+            match token {
+                TexToken::BeginGroup => n_open_paren += 1,
+                TexToken::EndGroup   => n_open_paren = n_open_paren.saturating_sub(1),
+                _ if token == closing && n_open_paren == 0 => return Ok(math),
+                _ => (),
+            }
+            math.push(token);
+        }
+        Err(ParseError::UnterminatedInlineMath)
+    }
+
+    /// Ends the current text run, then adds the math ; the math is grouped so that its spacing does not depend on the surrounding text
+    fn push_math(&mut self, math : Vec<TexToken<'a>>) -> ParseResult<()> {
+        if !self.run.is_empty() {
+            self.end_run();
+        }
+        let math = self.parser.parse_tokens_as_nodes(math)?;
+        self.nodes.push(ParseNode::Group(math));
+        Ok(())
+    }
+
+    /// Adds characters set in the given font to the current text run, or to a new run if the font changes
+    fn push_str(&mut self, text : &str, style : Style) {
+        if style != self.run_style {
+            if !self.run.is_empty() {
+                self.end_run();
+            }
+            self.run_style = style;
+        }
+        self.run.push_str(text);
+    }
+
+    fn end_run(&mut self) {
+        let text = std::mem::take(&mut self.run);
+        self.nodes.push(ParseNode::PlainText(PlainText { text, style: self.run_style }));
+    }
+}
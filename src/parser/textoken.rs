@@ -73,6 +73,8 @@ impl<'a> Iterator for TokenIterator<'a> {
                             .unwrap_or_else(|| beginning_control_seq.len()); // or the rest of the string is alphanumeric
                         input_processor.stream = &beginning_control_seq[index ..];
                         control_sequence_name = &beginning_control_seq[.. index];
+                        // like in TeX, whitespaces following the name of a control word are skipped
+                        input_processor.skip_whitespace();
                     }
                     else {
                         // the control sequence does not start with an alphanumeric character
@@ -80,9 +82,6 @@ impl<'a> Iterator for TokenIterator<'a> {
                         input_processor.stream = rest;
                         control_sequence_name = &beginning_control_seq[.. control_seq_first_char.len_utf8()];
                     }
-                    // Either way, skip whitespaces following the control sequence name
-                    // and return name
-                    input_processor.skip_whitespace();
                     Some(TexToken::ControlSequence(control_sequence_name))
                 }
                 else {
@@ -131,7 +130,8 @@ impl<'a> Iterator for TokenIterator<'a> {
                 Some(TexToken::EndGroup)                
             }
             '#' => {
-                if let Some((rest, arg_index)) = split_string_integer(rest) {
+                // Parameters are numbered from 1: `#0..` is kept as characters, so that colors like `#00ff00` are read in full
+                if let Some((rest, arg_index)) = split_string_integer(rest).filter(|_| !rest.starts_with('0')) {
                     input_processor.stream = rest;
                     Some(TexToken::Argument(arg_index))
                 }
@@ -0,0 +1,438 @@
+//! Formats numbers and physical quantities for `\num{..}`, `\si{..}` (or `\unit{..}`) and `\SI{..}{..}` (or `\qty{..}{..}`), following (a subset of) the siunitx LaTeX package.
+//!
+//! Numbers may have a sign, a decimal part (with `.` or `,` as decimal marker), an uncertainty and an exponent, e.g. `-1.234+-0.005e-3` ;
+//! several numbers can be multiplied with `x` (e.g. `2x3x4`). The digits of parts with at least 5 digits are grouped by three.
+//!
+//! Units are set upright and separated by thin spaces. They can be given as:
+//!   - macros, e.g. `\kilo\metre\per\second\squared` (cf `prefix_symbol` and `unit_symbol`), where `\per` raises the following unit to a negative power
+//!   - literals, e.g. `kg.m/s^2`, where `.`, `~` and spaces separate the units.
+
+use unicode_math::TexSymbolType;
+
+use super::control_sequence::SpaceKind;
+use super::error::{ParseError, ParseResult};
+use super::nodes::{PlainText, Scripts};
+use super::symbols::Symbol;
+use super::text::TEXT_STYLE;
+use super::textoken::TexToken;
+use super::{script_nodes, tokens_as_string, upright, ParseNode};
+
+/// A part of a number (integer or decimal part) is only grouped when it has at least this many digits
+const GROUP_MINIMUM_DIGITS : usize = 5;
+
+/// Units which are not separated from the number they follow (e.g. `\SI{30}{\degree}`)
+const ANGLE_UNITS : [&str; 3] = ["°", "′", "″"];
+
+/// Parses the argument of `\num{..}`.
+pub fn parse_number(input : &str) -> ParseResult<Vec<ParseNode>> {
+    let number : String = input.chars().filter(|c| !c.is_whitespace()).collect();
+    let mut nodes = Vec::new();
+    for (i, factor) in number.split('x').enumerate() {
+        if i > 0 {
+            nodes.push(ParseNode::Symbol(Symbol { codepoint: '×', atom_type: TexSymbolType::Binary }));
+        }
+        let factor = real_number(factor).ok_or_else(|| ParseError::UnrecognizedNumber(Box::from(input)))?;
+        nodes.extend(factor);
+    }
+    Ok(nodes)
+}
+
+/// Parses the tokens in the argument of `\si{..}`.
+pub fn parse_unit<'a>(tokens : Vec<TexToken<'a>>) -> ParseResult<Vec<ParseNode>> {
+    layout_unit(UnitParser::new(tokens).parse()?)
+}
+
+/// Parses the arguments of `\SI{..}{..}` ; the number and the unit are separated by a thin space, except for angles.
+pub fn parse_quantity<'a>(number : &str, unit : Vec<TexToken<'a>>) -> ParseResult<Vec<ParseNode>> {
+    let mut nodes = parse_number(number)?;
+    let unit = UnitParser::new(unit).parse()?;
+    let is_angle = matches!(unit.first(), Some(UnitItem::Term(term)) if ANGLE_UNITS.contains(&term.symbol.as_str()));
+    if !is_angle {
+        nodes.push(ParseNode::Kerning(SpaceKind::ThinSpace.size()));
+    }
+    nodes.extend(layout_unit(unit)?);
+    Ok(nodes)
+}
+
+/// Parses a number with an optional uncertainty and exponent, e.g. `-1.234+-0.005e-3`.
+/// If both are present, the number and its uncertainty are enclosed in parentheses.
+fn real_number(input : &str) -> Option<Vec<ParseNode>> {
+    let (mantissa, exponent) = match input.find(['e', 'E']) {
+        Some(index) => (&input[.. index], Some(&input[index + 1 ..])),
+        None        => (input, None),
+    };
+    // A leading `+-` is a sign, not an uncertainty
+    let (value, uncertainty) = match mantissa.get(1 ..).and_then(|rest| rest.find("+-")) {
+        Some(index) => (&mantissa[.. index + 1], Some(&mantissa[index + 3 ..])),
+        None        => (mantissa, None),
+    };
+
+    let mut nodes = Vec::new();
+    let (sign, value) = split_sign(value);
+    nodes.extend(sign);
+    if !value.is_empty() || exponent.is_none() {
+        nodes.extend(decimal(value)?);
+    }
+    if let Some(uncertainty) = uncertainty {
+        nodes.push(ParseNode::Symbol(Symbol { codepoint: '±', atom_type: TexSymbolType::Binary }));
+        nodes.extend(decimal(uncertainty)?);
+        if exponent.is_some() {
+            nodes.insert(0, ParseNode::Symbol(Symbol { codepoint: '(', atom_type: TexSymbolType::Open }));
+            nodes.push(ParseNode::Symbol(Symbol { codepoint: ')', atom_type: TexSymbolType::Close }));
+        }
+    }
+
+    if let Some(exponent) = exponent {
+        let (sign, digits) = split_sign(exponent);
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        if !value.is_empty() {
+            nodes.push(ParseNode::Symbol(Symbol { codepoint: '×', atom_type: TexSymbolType::Binary }));
+        }
+        let mut superscript : Vec<ParseNode> = sign.into_iter().collect();
+        superscript.extend(digits.chars().map(upright));
+        nodes.push(ParseNode::Scripts(Scripts {
+            base: Some(Box::new(ParseNode::Group(vec![upright('1'), upright('0')]))),
+            superscript: Some(superscript),
+            subscript: None,
+            presuperscript: None,
+            presubscript: None,
+        }));
+    }
+    Some(nodes)
+}
+
+/// Separates the sign (`+`, `-` or `+-`) from the rest of a number
+fn split_sign(input : &str) -> (Option<ParseNode>, &str) {
+    let (codepoint, rest) =
+        if let Some(rest) = input.strip_prefix("+-") { ('±', rest) }
+        else if let Some(rest) = input.strip_prefix('+') { ('+', rest) }
+        else if let Some(rest) = input.strip_prefix('-') { ('−', rest) }
+        else { return (None, input) };
+    (Some(ParseNode::Symbol(Symbol { codepoint, atom_type: TexSymbolType::Ordinary })), rest)
+}
+
+/// Parses an unsigned decimal number, e.g. `1234.5` or `,5` ; a missing integer part is replaced by `0`.
+fn decimal(input : &str) -> Option<Vec<ParseNode>> {
+    let (integer, fractional) = match input.find(['.', ',']) {
+        Some(index) => (&input[.. index], Some(&input[index + 1 ..])),
+        None        => (input, None),
+    };
+    let is_digits = |digits : &str| digits.chars().all(|c| c.is_ascii_digit());
+    if !is_digits(integer) || matches!(fractional, Some(fractional) if !is_digits(fractional)) || (integer.is_empty() && matches!(fractional, None | Some(""))) {
+        return None;
+    }
+
+    let mut nodes = if integer.is_empty() { vec![upright('0')] } else { group_digits(integer, false) };
+    if let Some(fractional) = fractional.filter(|digits| !digits.is_empty()) {
+        nodes.push(ParseNode::Symbol(Symbol { codepoint: '.', atom_type: TexSymbolType::Alpha }));
+        nodes.extend(group_digits(fractional, true));
+    }
+    Some(nodes)
+}
+
+/// Separates groups of three digits with thin spaces, counting from the start of the digits (for a decimal part) or from their end (for an integer part)
+fn group_digits(digits : &str, from_start : bool) -> Vec<ParseNode> {
+    let length = digits.len();
+    let mut nodes = Vec::with_capacity(length);
+    for (i, c) in digits.chars().enumerate() {
+        let position = if from_start { i } else { length - i };
+        if i > 0 && length >= GROUP_MINIMUM_DIGITS && position % 3 == 0 {
+            nodes.push(ParseNode::Kerning(SpaceKind::ThinSpace.size()));
+        }
+        nodes.push(upright(c));
+    }
+    nodes
+}
+
+/// A unit raised to some power, e.g. `\square\metre` or `s^{-1}`
+struct UnitTerm {
+    symbol : String,
+    power  : Option<String>,
+    /// Whether the unit follows `\per`, i.e. whether its power is negated
+    per : bool,
+}
+
+enum UnitItem {
+    Term(UnitTerm),
+    /// A literal `/`
+    Slash,
+}
+
+struct UnitParser<'a> {
+    tokens : std::iter::Peekable<std::vec::IntoIter<TexToken<'a>>>,
+    items  : Vec<UnitItem>,
+    /// Prefixes (e.g. `\kilo`), power (e.g. `\square`) and `\per` given before the next unit
+    prefix : String,
+    power  : Option<String>,
+    per    : bool,
+    /// Whether the last token is a character of a literal unit (e.g. the `k` of `kg`)
+    in_literal : bool,
+}
+
+impl<'a> UnitParser<'a> {
+    fn new(tokens : Vec<TexToken<'a>>) -> Self {
+        Self {
+            tokens : tokens.into_iter().peekable(),
+            items : Vec::new(),
+            prefix : String::new(),
+            power : None,
+            per : false,
+            in_literal : false,
+        }
+    }
+
+    fn parse(mut self) -> ParseResult<Vec<UnitItem>> {
+        while let Some(token) = self.tokens.next() {
+            let in_literal = std::mem::replace(&mut self.in_literal, false);
+            match token {
+                TexToken::ControlSequence(",") | TexToken::ControlSequence(" ") => (),
+                TexToken::ControlSequence(name) => self.command(name)?,
+                TexToken::WhiteSpace | TexToken::Tilde | TexToken::Char('.') => (),
+                TexToken::Char('/') => self.items.push(UnitItem::Slash),
+                TexToken::Superscript => {
+                    let power = self.argument()?;
+                    self.raise_last(power)?;
+                },
+                TexToken::BeginGroup | TexToken::EndGroup => self.in_literal = in_literal,
+                TexToken::Char(c) => {
+                    match self.items.last_mut() {
+                        Some(UnitItem::Term(term)) if in_literal => term.symbol.push(c),
+                        _ => self.push_term(&c.to_string()),
+                    }
+                    self.in_literal = true;
+                },
+                _ => return Err(ParseError::ExpectedChars),
+            }
+        }
+
+        if !self.prefix.is_empty() || self.power.is_some() || self.per {
+            return Err(ParseError::ExpectedUnit);
+        }
+        Ok(self.items)
+    }
+
+    fn command(&mut self, name : &str) -> ParseResult<()> {
+        if let Some(symbol) = unit_symbol(name) {
+            self.push_term(symbol);
+            return Ok(());
+        }
+        if let Some(prefix) = prefix_symbol(name) {
+            self.prefix.push_str(prefix);
+            return Ok(());
+        }
+
+        match name {
+            "per"     => self.per = true,
+            "square"  => self.set_power(String::from("2"))?,
+            "cubic"   => self.set_power(String::from("3"))?,
+            "raiseto" => {
+                let power = self.argument()?;
+                self.set_power(power)?;
+            },
+            "squared" => self.raise_last(String::from("2"))?,
+            "cubed"   => self.raise_last(String::from("3"))?,
+            "tothe"   => {
+                let power = self.argument()?;
+                self.raise_last(power)?;
+            },
+            _ => return Err(ParseError::UnrecognizedUnit(Box::from(name))),
+        }
+        Ok(())
+    }
+
+    /// Reads the argument of `^`, `\tothe` or `\raiseto`: either a group or a single character
+    fn argument(&mut self) -> ParseResult<String> {
+        while let Some(TexToken::WhiteSpace) = self.tokens.peek() {
+            self.tokens.next();
+        }
+        match self.tokens.next() {
+            Some(TexToken::BeginGroup) => {
+                let mut depth = 1;
+                let group = self.tokens.by_ref().take_while(|token| {
+                    match token {
+                        TexToken::BeginGroup => depth += 1,
+                        TexToken::EndGroup   => depth -= 1,
+                        _ => (),
+                    }
+                    depth > 0
+                });
+                let argument = tokens_as_string(group)?;
+                if depth > 0 {
+                    return Err(ParseError::UnmatchedBrackets);
+                }
+                Ok(argument)
+            },
+            Some(TexToken::Char(c)) => Ok(c.to_string()),
+            Some(_) => Err(ParseError::ExpectedChars),
+            None => Err(ParseError::MissingSubSuperScript),
+        }
+    }
+
+    fn push_term(&mut self, symbol : &str) {
+        let mut prefixed = std::mem::take(&mut self.prefix);
+        prefixed.push_str(symbol);
+        self.items.push(UnitItem::Term(UnitTerm {
+            symbol : prefixed,
+            power  : self.power.take(),
+            per    : std::mem::take(&mut self.per),
+        }));
+    }
+
+    /// Sets the power of the next unit
+    fn set_power(&mut self, power : String) -> ParseResult<()> {
+        if self.power.is_some() {
+            return Err(ParseError::TooManySubscriptsOrSuperscripts);
+        }
+        self.power = Some(power);
+        Ok(())
+    }
+
+    /// Sets the power of the last unit
+    fn raise_last(&mut self, power : String) -> ParseResult<()> {
+        match self.items.last_mut() {
+            Some(UnitItem::Term(UnitTerm { power: Some(_), .. })) => Err(ParseError::TooManySubscriptsOrSuperscripts),
+            Some(UnitItem::Term(term)) => {
+                term.power = Some(power);
+                Ok(())
+            },
+            _ => Err(ParseError::ExpectedUnit),
+        }
+    }
+}
+
+fn layout_unit(items : Vec<UnitItem>) -> ParseResult<Vec<ParseNode>> {
+    let mut nodes = Vec::with_capacity(2 * items.len());
+    let mut after_term = false;
+    for item in items {
+        match item {
+            UnitItem::Term(UnitTerm { symbol, power, per }) => {
+                if after_term {
+                    nodes.push(ParseNode::Kerning(SpaceKind::ThinSpace.size()));
+                }
+                let power = match (power, per) {
+                    (power, false) => power,
+                    (None, true)   => Some(String::from("-1")),
+                    (Some(power), true) => Some(match power.strip_prefix('-') {
+                        Some(positive) => positive.to_string(),
+                        None           => format!("-{}", power),
+                    }),
+                };
+                let base = ParseNode::PlainText(PlainText { text: symbol, style: TEXT_STYLE });
+                nodes.push(match power {
+                    None => base,
+                    Some(power) => ParseNode::Scripts(Scripts {
+                        base: Some(Box::new(base)),
+                        superscript: Some(script_nodes(&power)?),
+                        subscript: None,
+                        presuperscript: None,
+                        presubscript: None,
+                    }),
+                });
+                after_term = true;
+            },
+            UnitItem::Slash => {
+                nodes.push(ParseNode::Symbol(Symbol { codepoint: '/', atom_type: TexSymbolType::Alpha }));
+                after_term = false;
+            },
+        }
+    }
+    Ok(nodes)
+}
+
+/// The symbols of the SI prefixes, e.g. `k` for `\kilo`
+fn prefix_symbol(name : &str) -> Option<&'static str> {
+    Some(match name {
+        "quecto" => "q",
+        "ronto"  => "r",
+        "yocto"  => "y",
+        "zepto"  => "z",
+        "atto"   => "a",
+        "femto"  => "f",
+        "pico"   => "p",
+        "nano"   => "n",
+        "micro"  => "μ",
+        "milli"  => "m",
+        "centi"  => "c",
+        "deci"   => "d",
+        "deca" | "deka" => "da",
+        "hecto"  => "h",
+        "kilo"   => "k",
+        "mega"   => "M",
+        "giga"   => "G",
+        "tera"   => "T",
+        "peta"   => "P",
+        "exa"    => "E",
+        "zetta"  => "Z",
+        "yotta"  => "Y",
+        "ronna"  => "R",
+        "quetta" => "Q",
+        _ => return None,
+    })
+}
+
+/// The symbols of the units, e.g. `m` for `\metre`
+fn unit_symbol(name : &str) -> Option<&'static str> {
+    Some(match name {
+        // SI base units
+        "ampere"   => "A",
+        "candela"  => "cd",
+        "kelvin"   => "K",
+        "kilogram" => "kg",
+        "gram"     => "g",
+        "metre" | "meter" => "m",
+        "mole"     => "mol",
+        "second"   => "s",
+
+        // SI derived units
+        "becquerel"      => "Bq",
+        "degreeCelsius"  => "°C",
+        "coulomb"        => "C",
+        "farad"          => "F",
+        "gray"           => "Gy",
+        "hertz"          => "Hz",
+        "henry"          => "H",
+        "joule"          => "J",
+        "katal"          => "kat",
+        "lumen"          => "lm",
+        "lux"            => "lx",
+        "newton"         => "N",
+        "ohm"            => "Ω",
+        "pascal"         => "Pa",
+        "radian"         => "rad",
+        "siemens"        => "S",
+        "sievert"        => "Sv",
+        "steradian"      => "sr",
+        "tesla"          => "T",
+        "volt"           => "V",
+        "watt"           => "W",
+        "weber"          => "Wb",
+
+        // Units accepted for use with the SI
+        "astronomicalunit" => "au",
+        "bel"          => "B",
+        "dalton"       => "Da",
+        "day"          => "d",
+        "decibel"      => "dB",
+        "degree"       => "°",
+        "electronvolt" => "eV",
+        "hectare"      => "ha",
+        "hour"         => "h",
+        "litre" | "liter" => "L",
+        "arcminute"    => "′",
+        "minute"       => "min",
+        "arcsecond"    => "″",
+        "neper"        => "Np",
+        "tonne"        => "t",
+
+        // Other units
+        "percent"   => "%",
+        "angstrom"  => "Å",
+        "bar"       => "bar",
+        "barn"      => "b",
+        "mmHg"      => "mmHg",
+        "knot"      => "kn",
+        _ => return None,
+    })
+}
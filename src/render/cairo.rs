@@ -63,6 +63,14 @@ impl GraphicsBackend for CairoBackend {
         context.fill().unwrap();
     }
 
+    fn line(&mut self, start: crate::Cursor, end: crate::Cursor, thickness: f64) {
+        let context = &self.context;
+        context.set_line_width(thickness);
+        context.move_to(start.x, start.y);
+        context.line_to(end.x, end.y);
+        context.stroke().unwrap();
+    }
+
 
     fn begin_color(&mut self, color: crate::RGBA) {
         let old_color = std::mem::replace(&mut self.current_color, (color.0, color.1, color.2, color.3,));
@@ -205,6 +205,15 @@ impl<'a, T : Renderer> GraphicsBackend for FemtoVGCanvas<'a, T> {
         self.canvas.fill_path(&mut path, &self.current_paint)
     }
 
+    fn line(&mut self, start: crate::Cursor, end: crate::Cursor, thickness: f64) {
+        let mut path = femtovg::Path::new();
+        path.move_to(start.x as f32, start.y as f32);
+        path.line_to(end.x as f32, end.y as f32);
+
+        let paint = self.current_paint.clone().with_line_width(thickness as f32);
+        self.canvas.stroke_path(&mut path, &paint)
+    }
+
     fn begin_color(&mut self, color: crate::RGBA) {
         let color = femtovg::Color::rgba(color.0, color.1, color.2, color.3);
         let paint = femtovg::Paint::color(color)
@@ -28,7 +28,7 @@ use crate::error::Error;
 use crate::dimensions::units::Px;
 use crate::font::MathFont;
 use crate::font::common::GlyphId;
-use crate::layout::{LayoutNode, LayoutVariant, Alignment, LayoutSettings, Layout, Grid};
+use crate::layout::{LayoutNode, LayoutVariant, Alignment, LayoutSettings, Layout, Grid, LineSegment};
 pub use crate::parser::color::RGBA;
 
 /// Context used for rendering.
@@ -109,6 +109,35 @@ pub trait GraphicsBackend {
     fn bbox(&mut self, _pos: Cursor, _width: f64, _height: f64, _role: Role) {}
     /// Draws a filled rectangle whose top-left corner is at `pos`. Used to draw fraction bars and radicals.
     fn rule(&mut self, pos: Cursor, width: f64, height: f64);
+    /// Draws a rectangle whose top-left corner is at `pos`, filled with `color` rather than the current color.
+    /// Used to draw backgrounds (e.g. `\colorbox{..}{..}`), which come before the glyphs they lie beneath.  
+    /// The default implementation wraps [`GraphicsBackend::rule`] between [`GraphicsBackend::begin_color`] and [`GraphicsBackend::end_color`].
+    fn filled_rect(&mut self, pos: Cursor, width: f64, height: f64, color: RGBA) {
+        self.begin_color(color);
+        self.rule(pos, width, height);
+        self.end_color();
+    }
+    /// Draws a straight line of the given `thickness` from `start` to `end` in the current color. Used to strike out formulas (e.g. `\cancel{..}`).  
+    /// The default implementation approximates the line with squares of side `thickness` drawn with [`GraphicsBackend::rule`] ;
+    /// a horizontal or vertical line is drawn as a single thin rectangle.
+    fn line(&mut self, start: Cursor, end: Cursor, thickness: f64) {
+        let (dx, dy) = (end.x - start.x, end.y - start.y);
+        if dy == 0. {
+            self.rule(Cursor { x: start.x.min(end.x), y: start.y - thickness / 2. }, dx.abs(), thickness);
+            return;
+        }
+        if dx == 0. {
+            self.rule(Cursor { x: start.x - thickness / 2., y: start.y.min(end.y) }, thickness, dy.abs());
+            return;
+        }
+
+        let n_steps = (dx.hypot(dy) / thickness).ceil().max(1.);
+        for i in 0 ..= n_steps as usize {
+            let t = i as f64 / n_steps;
+            let pos = Cursor { x: start.x + t * dx - thickness / 2., y: start.y + t * dy - thickness / 2. };
+            self.rule(pos, thickness, thickness);
+        }
+    }
     /// Makes `color` the current used color. The color previously in use is restored with [`GraphicsBackend::end_color`].
     fn begin_color(&mut self, color: RGBA);
     /// Restores the previously used color. If there were no previous color, this function should return silently and not panic.
@@ -193,6 +222,8 @@ impl Renderer {
         for node in nodes {
             match node.node {
                 LayoutVariant::Rule => out.rule(pos, node.width.unitless(Px), node.height.unitless(Px)),
+                LayoutVariant::FilledRect(color) => out.filled_rect(pos, node.width.unitless(Px), (node.height - node.depth).unitless(Px), color),
+                LayoutVariant::Line(ref line) => render_line(out, pos.down(node.height.unitless(Px)), line),
                 LayoutVariant::Grid(ref grid) => self.render_grid(out, pos, node.height.unitless(Px), node.width.unitless(Px), grid),
                 LayoutVariant::HorizontalBox(ref hbox) => {
                     self.render_hbox(out,
@@ -237,6 +268,10 @@ impl Renderer {
 
             LayoutVariant::Rule => out.rule(pos.up(node.height.unitless(Px)), node.width.unitless(Px), node.height.unitless(Px)),
 
+            LayoutVariant::FilledRect(color) => out.filled_rect(pos.up(node.height.unitless(Px)), node.width.unitless(Px), (node.height - node.depth).unitless(Px), color),
+
+            LayoutVariant::Line(ref line) => render_line(out, pos, line),
+
             LayoutVariant::VerticalBox(ref vbox) => {
                 if self.debug {
                     out.bbox(pos.up(node.height.unitless(Px)), node.width.unitless(Px), (node.height - node.depth).unitless(Px), Role::VBox);
@@ -261,6 +296,17 @@ impl Renderer {
     }
 }
 
+/// Draws `line`, whose end points are relative to `baseline` (the left end of the baseline of its node).
+fn render_line<F>(out: &mut impl Backend<F>, baseline: Cursor, line: &LineSegment) {
+    let (start_x, start_y) = line.start;
+    let (end_x, end_y) = line.end;
+    out.line(
+        baseline.translate(start_x.unitless(Px), -start_y.unitless(Px)),
+        baseline.translate(end_x.unitless(Px), -end_y.unitless(Px)),
+        line.thickness.unitless(Px)
+    );
+}
+
 #[cfg(feature="pathfinder-renderer")]
 pub mod pathfinder;
 #[cfg(feature="femtovg-renderer")]
@@ -3,7 +3,7 @@
 //! The type [`RaqoteBackend`] is a wrapper around [`DrawTarget`] that implements [`Backend`].
 //! With this, you can render a given formula to a `raqote` draw target.
 
-use raqote::{DrawTarget, Source, SolidSource, DrawOptions, Transform, PathBuilder};
+use raqote::{DrawTarget, Source, SolidSource, DrawOptions, Transform, PathBuilder, StrokeStyle};
 
 use crate::{Backend, font::backend::ttf_parser::TtfMathFont, GraphicsBackend, FontBackend};
 
@@ -53,6 +53,19 @@ impl<'dt> GraphicsBackend for RaqoteBackend<'dt> {
         self.target.fill(&path, &Source::Solid(self.current_color), &DrawOptions::default());
     }
 
+    fn line(&mut self, start: crate::Cursor, end: crate::Cursor, thickness: f64) {
+        let mut path_builder = raqote::PathBuilder::new();
+        path_builder.move_to(start.x as f32, start.y as f32);
+        path_builder.line_to(end.x as f32, end.y as f32);
+        let path = path_builder.finish();
+
+        let style = StrokeStyle {
+            width: thickness as f32,
+            ..StrokeStyle::default()
+        };
+        self.target.stroke(&path, &Source::Solid(self.current_color), &style, &DrawOptions::default());
+    }
+
 
     fn begin_color(&mut self, color: crate::RGBA) {
         self.color_stack.push(self.current_color);
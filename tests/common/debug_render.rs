@@ -38,6 +38,11 @@ pub enum DrawCmd {
         width  : f64,
         height : f64,
     },
+    Line {
+        start     : (f64, f64),
+        end       : (f64, f64),
+        thickness : f64,
+    },
     Symbol {
         pos      : (f64, f64),
         glyph_id : u16,
@@ -55,6 +60,14 @@ impl GraphicsBackend for DebugRender {
         })
     }
 
+    fn line(&mut self, start: rex::Cursor, end: rex::Cursor, thickness: f64) {
+        self.commands.push(DrawCmd::Line {
+            start: (start.x, start.y),
+            end:   (end.x, end.y),
+            thickness,
+        })
+    }
+
     fn begin_color(&mut self, _color: rex::RGBA) {
     }
 